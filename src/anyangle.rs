@@ -0,0 +1,241 @@
+use crate::{walk_backwards, Edge, Grid, Node, NodeMap, NodeSet, Path, Weight};
+
+use std::collections::BinaryHeap;
+
+struct Search<'a> {
+    grid: &'a Grid,
+    goal: Node,
+    node_cost: NodeMap<Weight>,
+    parents: NodeMap<Edge>,
+    closed: NodeSet,
+    queue: BinaryHeap<Edge>,
+}
+
+impl<'a> Search<'a> {
+    fn new(grid: &'a Grid, start: Node, goal: Node) -> Self {
+        let mut s = Self {
+            grid,
+            goal,
+            node_cost: NodeMap::with_capacity(grid.len()),
+            parents: NodeMap::with_capacity(grid.len()),
+            closed: NodeSet::with_capacity(grid.len()),
+            queue: BinaryHeap::new(),
+        };
+        s.node_cost.insert(start, 0.0);
+        s.parents.insert(
+            start,
+            Edge {
+                node: start,
+                weight: 0.0,
+            },
+        );
+        s.queue.push(Edge {
+            node: start,
+            weight: 0.0,
+        });
+        s
+    }
+
+    fn distance(&self, a: Node, b: Node) -> Weight {
+        self.grid.distance(self.grid.coords(a), self.grid.coords(b))
+    }
+
+    fn line_of_sight(&self, a: Node, b: Node) -> bool {
+        self.grid
+            .line_of_sight(self.grid.coords(a), self.grid.coords(b))
+    }
+
+    fn parent(&self, n: Node) -> Node {
+        self.parents[n].node
+    }
+
+    /// Pop the next node to expand, skipping stale queue entries
+    fn pop(&mut self) -> Option<Node> {
+        while let Some(Edge { node, .. }) = self.queue.pop() {
+            if self.closed.add(node) {
+                return Some(node);
+            }
+        }
+        None
+    }
+
+    fn neighbors(&self, n: Node) -> impl Iterator<Item = Node> + '_ {
+        self.grid
+            .neighbors(self.grid.coords(n))
+            .map(|(c, _)| self.grid.node(c))
+    }
+
+    fn relax(&mut self, child: Node, parent: Node, cost: Weight) {
+        if !self.node_cost.has(&child) || cost < self.node_cost[child] {
+            self.node_cost.insert(child, cost);
+            self.parents.insert(
+                child,
+                Edge {
+                    node: parent,
+                    weight: self.distance(parent, child),
+                },
+            );
+            let estimated_end_cost = cost + self.distance(child, self.goal);
+            self.queue.push(Edge {
+                node: child,
+                weight: estimated_end_cost,
+            });
+        }
+    }
+}
+
+fn passable(grid: &Grid, n: Node) -> bool {
+    grid.is_passable(grid.coords(n))
+}
+
+/// Any-angle shortest path on a grid.
+///
+/// Like A* on the 8-connected grid, but a node may take its grandparent as parent if there is
+/// line of sight between them, which gives paths with segments at arbitrary angles. The weights
+/// in the returned path are the euclidean lengths of the segments. Returns None if the goal can't
+/// be reached, which includes a blocked start or goal tile.
+pub fn theta_star(grid: &Grid, start: Node, goal: Node) -> Option<Path> {
+    if grid.is_empty() || start == goal || !passable(grid, start) || !passable(grid, goal) {
+        return None;
+    }
+
+    let mut s = Search::new(grid, start, goal);
+    while let Some(cur) = s.pop() {
+        if cur == goal {
+            return walk_backwards(&start, &goal, &s.parents);
+        }
+
+        let parent = s.parent(cur);
+        let children: Vec<Node> = s.neighbors(cur).collect();
+        for child in children {
+            if s.closed.has(child) {
+                continue;
+            }
+
+            if s.line_of_sight(parent, child) {
+                let cost = s.node_cost[parent] + s.distance(parent, child);
+                s.relax(child, parent, cost);
+            } else {
+                let cost = s.node_cost[cur] + s.distance(cur, child);
+                s.relax(child, cur, cost);
+            }
+        }
+    }
+
+    None
+}
+
+/// Same as `theta_star` but defers the line of sight check until a node is expanded, which
+/// results in far fewer checks at the cost of sometimes slightly longer paths.
+pub fn lazy_theta_star(grid: &Grid, start: Node, goal: Node) -> Option<Path> {
+    if grid.is_empty() || start == goal || !passable(grid, start) || !passable(grid, goal) {
+        return None;
+    }
+
+    let mut s = Search::new(grid, start, goal);
+    while let Some(cur) = s.pop() {
+        let parent = s.parent(cur);
+        if cur != start && !s.line_of_sight(parent, cur) {
+            // The optimistic parent was wrong, pick the best expanded neighbor instead. There is
+            // always one since cur was reached through a neighbor.
+            let (best, cost) = s
+                .neighbors(cur)
+                .filter(|n| s.closed.has(*n) && *n != cur)
+                .map(|n| (n, s.node_cost[n] + s.distance(n, cur)))
+                .min_by(|a, b| a.1.partial_cmp(&b.1).expect("Invalid float"))
+                .expect("Expanded node without expanded neighbor");
+            s.node_cost.insert(cur, cost);
+            s.parents.insert(
+                cur,
+                Edge {
+                    node: best,
+                    weight: s.distance(best, cur),
+                },
+            );
+        }
+
+        if cur == goal {
+            return walk_backwards(&start, &goal, &s.parents);
+        }
+
+        let parent = s.parent(cur);
+        let children: Vec<Node> = s.neighbors(cur).collect();
+        for child in children {
+            if s.closed.has(child) {
+                continue;
+            }
+
+            let cost = s.node_cost[parent] + s.distance(parent, child);
+            s.relax(child, parent, cost);
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{path_cost, Grid};
+
+    // ......
+    // ......
+    // ..##..
+    // ..##..
+    // ......
+    fn walled() -> Grid {
+        Grid::new(6, 5, |(x, y)| !((2..4).contains(&x) && (2..4).contains(&y)))
+    }
+
+    #[test]
+    fn open_field_is_straight() {
+        let grid = Grid::new(8, 8, |_| true);
+        let (start, goal) = (grid.node((0, 0)), grid.node((7, 3)));
+        for path in [
+            super::theta_star(&grid, start, goal).unwrap(),
+            super::lazy_theta_star(&grid, start, goal).unwrap(),
+        ] {
+            assert_eq!(path.len(), 2);
+            assert_eq!(path[0].node, start);
+            assert_eq!(path[1].node, goal);
+            assert!((path_cost(&path) - grid.distance((0, 0), (7, 3))).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn around_wall() {
+        let grid = walled();
+        let (start, goal) = (grid.node((0, 4)), grid.node((5, 0)));
+        let grid_cost = crate::multi_source_dijkstra(&grid.to_graph(), &[start])[goal];
+        for path in [
+            super::theta_star(&grid, start, goal).unwrap(),
+            super::lazy_theta_star(&grid, start, goal).unwrap(),
+        ] {
+            assert_eq!(path.first().unwrap().node, start);
+            assert_eq!(path.last().unwrap().node, goal);
+            for w in path.windows(2) {
+                assert!(grid.line_of_sight(grid.coords(w[0].node), grid.coords(w[1].node)));
+            }
+            assert!(path.len() > 2);
+            assert!(path_cost(&path) < grid_cost);
+        }
+    }
+
+    #[test]
+    fn unreachable() {
+        let grid = Grid::new(3, 1, |c| c != (1, 0));
+        let (start, goal) = (grid.node((0, 0)), grid.node((2, 0)));
+        assert!(super::theta_star(&grid, start, goal).is_none());
+        assert!(super::lazy_theta_star(&grid, start, goal).is_none());
+    }
+
+    #[test]
+    fn blocked_start_or_goal() {
+        let grid = Grid::new(4, 4, |c| c != (0, 0) && c != (3, 3));
+        let (blocked_start, blocked_goal) = (grid.node((0, 0)), grid.node((3, 3)));
+        let open = grid.node((2, 1));
+        for (start, goal) in [(blocked_start, open), (open, blocked_goal)] {
+            assert!(super::theta_star(&grid, start, goal).is_none());
+            assert!(super::lazy_theta_star(&grid, start, goal).is_none());
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{walk_backwards, AdjacencyList, Edge, Node, NodeMap, Path, Weight};
+use crate::{walk_backwards, AdjacencyList, Edge, Node, NodeMap, Path, Reachability, Weight};
 
 use std::collections::BinaryHeap;
 
@@ -56,6 +56,7 @@ pub struct AStarAcceleration {
     node_cost: NodeMap<Weight>,
     parents: NodeMap<Edge>,
     queue: BinaryHeap<Edge>,
+    reachability: Option<Reachability>,
 }
 
 impl AStarAcceleration {
@@ -66,9 +67,21 @@ impl AStarAcceleration {
             node_cost: NodeMap::with_capacity(len),
             parents: NodeMap::with_capacity(len),
             queue: BinaryHeap::with_capacity(len),
+            reachability: None,
         }
     }
 
+    /// Also precompute a `Reachability` index so that queries between disconnected nodes can be
+    /// rejected without searching.
+    ///
+    /// On directed graphs only some unreachable pairs are rejected, see `Reachability::may_reach`.
+    /// For the others `a_star` still searches everything reachable from the start.
+    pub fn with_reachability(g: &AdjacencyList) -> Self {
+        let mut acc = Self::new(g);
+        acc.reachability = Some(Reachability::new(g));
+        acc
+    }
+
     pub fn clear_transients(&mut self) {
         self.node_cost.clear();
         self.parents.clear();
@@ -80,6 +93,17 @@ pub trait HeuristicDistance {
     fn cost(&self, node: &Node) -> Weight;
 }
 
+/// Turns `a_star` into Dijkstra's algorithm, for tests that only care about the paths
+#[cfg(test)]
+pub(crate) struct NoHeuristic;
+
+#[cfg(test)]
+impl HeuristicDistance for NoHeuristic {
+    fn cost(&self, _: &Node) -> Weight {
+        0.0
+    }
+}
+
 /// Find the shortest path between two nodes
 pub fn a_star(
     acc: &mut AStarAcceleration,
@@ -94,6 +118,12 @@ pub fn a_star(
         return None;
     }
 
+    if let Some(reachability) = &acc.reachability {
+        if !reachability.may_reach(start, end) {
+            return None;
+        }
+    }
+
     let node_cost: &mut NodeMap<Weight> = &mut acc.node_cost;
     let parents: &mut NodeMap<Edge> = &mut acc.parents;
     let queue: &mut BinaryHeap<Edge> = &mut acc.queue;
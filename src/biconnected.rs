@@ -0,0 +1,257 @@
+use crate::{undirected_edges, undirected_graph, AdjacencyList, Node, NodeMap, NodeSet};
+
+/// The result of `biconnected_components`
+pub struct BiconnectedComponents {
+    /// Nodes whose removal disconnects their connected component
+    pub articulation_points: NodeSet,
+    /// Edges whose removal disconnects their connected component, as (a, b) with a < b
+    pub bridges: Vec<(Node, Node)>,
+    /// The nodes of each biconnected component, sorted. Articulation points are part of several
+    /// components and isolated nodes form a component of their own.
+    pub components: Vec<Vec<Node>>,
+    membership: NodeMap<Vec<usize>>,
+}
+
+/// A tree with one node per biconnected component (block) and one per articulation point, with
+/// edges between each articulation point and the blocks it is part of.
+pub struct BlockCutTree {
+    pub graph: AdjacencyList,
+    /// The tree node for each entry of `BiconnectedComponents::components`
+    pub blocks: Vec<Node>,
+    /// The tree node for each articulation point
+    pub cuts: NodeMap<Node>,
+}
+
+impl BiconnectedComponents {
+    /// Indices into `components` for the components that n is part of
+    pub fn components_of(&self, n: Node) -> &[usize] {
+        self.membership.get(&n).map_or(&[], |v| v.as_slice())
+    }
+
+    pub fn block_cut_tree(&self) -> BlockCutTree {
+        let mut graph = AdjacencyList::new();
+        let blocks: Vec<Node> = self.components.iter().map(|_| graph.add_node()).collect();
+        let mut cuts = NodeMap::new();
+        for (n, components) in self.membership.iter() {
+            if !self.articulation_points.has(n) {
+                continue;
+            }
+            let cut = graph.add_node();
+            cuts.insert(n, cut);
+            for &c in components {
+                graph.add_edge(cut, blocks[c], 1.0);
+                graph.add_edge(blocks[c], cut, 1.0);
+            }
+        }
+        BlockCutTree {
+            graph,
+            blocks,
+            cuts,
+        }
+    }
+}
+
+struct Frame {
+    node: Node,
+    parent: Option<Node>,
+    edge_idx: usize,
+}
+
+/// Find articulation points, bridges and biconnected components with an iterative version of the
+/// Hopcroft-Tarjan algorithm. The graph is treated as undirected.
+pub fn biconnected_components(g: &AdjacencyList) -> BiconnectedComponents {
+    const UNVISITED: usize = usize::MAX;
+
+    let undirected = undirected_graph(g.len(), &undirected_edges(g));
+    let len = undirected.len();
+    let mut disc = vec![UNVISITED; len];
+    let mut low = vec![0; len];
+    let mut time = 0;
+
+    let mut articulation_points = NodeSet::with_capacity(len);
+    let mut bridges = Vec::new();
+    let mut components: Vec<Vec<Node>> = Vec::new();
+    let mut edge_stack: Vec<(Node, Node)> = Vec::new();
+    let mut frames: Vec<Frame> = Vec::new();
+
+    for root in undirected.nodes() {
+        if disc[root.0] != UNVISITED {
+            continue;
+        }
+
+        disc[root.0] = time;
+        low[root.0] = time;
+        time += 1;
+        let mut root_children = 0;
+        frames.push(Frame {
+            node: root,
+            parent: None,
+            edge_idx: 0,
+        });
+
+        while let Some(frame) = frames.last_mut() {
+            let n = frame.node;
+            if let Some(e) = undirected.edges(n).nth(frame.edge_idx) {
+                frame.edge_idx += 1;
+                let child = e.node;
+                if Some(child) == frame.parent {
+                    continue;
+                }
+                if disc[child.0] == UNVISITED {
+                    edge_stack.push((n, child));
+                    disc[child.0] = time;
+                    low[child.0] = time;
+                    time += 1;
+                    frames.push(Frame {
+                        node: child,
+                        parent: Some(n),
+                        edge_idx: 0,
+                    });
+                } else if disc[child.0] < disc[n.0] {
+                    edge_stack.push((n, child));
+                    low[n.0] = low[n.0].min(disc[child.0]);
+                }
+                continue;
+            }
+
+            let parent = frames.pop().and_then(|f| f.parent);
+            let Some(p) = parent else {
+                continue;
+            };
+
+            low[p.0] = low[p.0].min(low[n.0]);
+            if p == root {
+                root_children += 1;
+            }
+
+            if low[n.0] > disc[p.0] {
+                bridges.push((p.min(n), p.max(n)));
+            }
+
+            if low[n.0] >= disc[p.0] {
+                if p != root {
+                    articulation_points.add(p);
+                }
+
+                let mut component = NodeSet::new();
+                while let Some((a, b)) = edge_stack.pop() {
+                    component.add(a);
+                    component.add(b);
+                    if (a, b) == (p, n) {
+                        break;
+                    }
+                }
+                components.push(component.to_vec());
+            }
+        }
+
+        if root_children > 1 {
+            articulation_points.add(root);
+        }
+        if root_children == 0 {
+            components.push(vec![root]);
+        }
+    }
+
+    bridges.sort();
+
+    let mut membership: NodeMap<Vec<usize>> = NodeMap::with_capacity(len);
+    for (i, component) in components.iter().enumerate() {
+        for &n in component {
+            match membership.get_mut(&n) {
+                Some(v) => v.push(i),
+                None => membership.insert(n, vec![i]),
+            }
+        }
+    }
+
+    BiconnectedComponents {
+        articulation_points,
+        bridges,
+        components,
+        membership,
+    }
+}
+
+/// Nodes whose removal disconnects their connected component. The graph is treated as undirected.
+pub fn articulation_points(g: &AdjacencyList) -> NodeSet {
+    biconnected_components(g).articulation_points
+}
+
+/// Edges whose removal disconnects their connected component, as (a, b) with a < b. The graph is
+/// treated as undirected.
+pub fn bridges(g: &AdjacencyList) -> Vec<(Node, Node)> {
+    biconnected_components(g).bridges
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{AdjacencyList, Node};
+
+    // Two triangles 0-1-2 and 3-4-5 joined by the bridge 2-3, with a tail 5-6 and 7 isolated
+    fn example() -> AdjacencyList {
+        let mut g = AdjacencyList::new();
+        let n: Vec<Node> = (0..8).map(|_| g.add_node()).collect();
+        for (a, b) in [
+            (0, 1),
+            (1, 2),
+            (2, 0),
+            (2, 3),
+            (3, 4),
+            (4, 5),
+            (5, 3),
+            (5, 6),
+        ] {
+            g.add_edge(n[a], n[b], 1.0);
+        }
+        g
+    }
+
+    #[test]
+    fn articulation_points_and_bridges() {
+        let g = example();
+        let points = crate::articulation_points(&g).to_vec();
+        assert_eq!(points, vec![Node(2), Node(3), Node(5)]);
+        let bridges = crate::bridges(&g);
+        assert_eq!(bridges, vec![(Node(2), Node(3)), (Node(5), Node(6))]);
+    }
+
+    #[test]
+    fn components() {
+        let g = example();
+        let bcc = crate::biconnected_components(&g);
+        let mut components = bcc.components.clone();
+        components.sort();
+        let expected: Vec<Vec<Node>> = vec![
+            vec![Node(0), Node(1), Node(2)],
+            vec![Node(2), Node(3)],
+            vec![Node(3), Node(4), Node(5)],
+            vec![Node(5), Node(6)],
+            vec![Node(7)],
+        ];
+        assert_eq!(components, expected);
+        assert_eq!(bcc.components_of(Node(3)).len(), 2);
+        assert_eq!(bcc.components_of(Node(0)).len(), 1);
+
+        let tree = bcc.block_cut_tree();
+        assert_eq!(tree.graph.len(), 5 + 3);
+        let cut = tree.cuts[Node(3)];
+        assert_eq!(tree.graph.edges(cut).count(), 2);
+        for &c in bcc.components_of(Node(3)) {
+            assert!(tree.graph.has_edge(cut, tree.blocks[c]));
+        }
+    }
+
+    #[test]
+    fn cycle_has_no_cuts() {
+        let mut g = AdjacencyList::new();
+        let n: Vec<Node> = (0..4).map(|_| g.add_node()).collect();
+        for i in 0..4 {
+            g.add_edge(n[i], n[(i + 1) % 4], 1.0);
+        }
+        let bcc = crate::biconnected_components(&g);
+        assert!(bcc.articulation_points.is_empty());
+        assert!(bcc.bridges.is_empty());
+        assert_eq!(bcc.components, vec![n.clone()]);
+    }
+}
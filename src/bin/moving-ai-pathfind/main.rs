@@ -1,8 +1,8 @@
 mod refimpl;
 
-use graf::{AdjacencyList, Edge, Node, NodeMap, Weight};
+use graf::{AdjacencyList, Edge, Grid, Node, NodeMap, Weight};
 
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use movingai::{Coords2D, Map2D as _};
 use movingai::{MovingAiMap, SceneRecord};
 
@@ -21,6 +21,16 @@ struct Cli {
     output_graph: Option<PathBuf>,
     #[arg(long)]
     output_map: Option<PathBuf>,
+    /// Use an any-angle search instead of A* on the 8-connected grid. The paths are then expected
+    /// to be at most as long as the reference ones.
+    #[arg(long)]
+    any_angle: Option<AnyAngle>,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum AnyAngle {
+    Theta,
+    LazyTheta,
 }
 
 #[allow(dead_code)]
@@ -117,7 +127,7 @@ mod dbg {
         let dump_dir = format!("dump/{}", scenario_idx);
         std::fs::create_dir_all(&dump_dir).unwrap();
         let ref_filepath = format!("{}/refpath.txt", dump_dir);
-        let ref_path = refimpl::shortest_path(&raw_map, scenario.start_pos, scenario.goal_pos);
+        let ref_path = refimpl::shortest_path(raw_map, scenario.start_pos, scenario.goal_pos);
         std::fs::write(ref_filepath, format!("{:#?}", ref_path)).unwrap();
 
         let result_filepath = format!("{}/failpath.txt", dump_dir);
@@ -126,8 +136,8 @@ mod dbg {
 
         let map_path = PathBuf::from(format!("{}/failmap.txt", dump_dir));
         dbg::dump_map_with_paths(
-            &graph,
-            &node2coord,
+            graph,
+            node2coord,
             raw_map.width(),
             raw_map.height(),
             Some(&result_path),
@@ -196,9 +206,10 @@ impl ScenarioResult {
 fn run_single_scenario(
     scenario: &movingai::SceneRecord,
     astar_acc: &mut graf::AStarAcceleration,
+    any_angle: Option<(AnyAngle, &Grid)>,
     coord2node: &HashMap<Coords2D, Node>,
     node2coord: &NodeMap<Coords2D>,
-) -> ScenarioResult {
+) -> Option<ScenarioResult> {
     let start = *coord2node
         .get(&scenario.start_pos)
         .expect("This should have a node assigned");
@@ -206,14 +217,19 @@ fn run_single_scenario(
         .get(&scenario.goal_pos)
         .expect("This should have a node assigned");
 
-    let heuristic = HeuristicDistance {
-        node2coord,
-        goal_pos: scenario.goal_pos,
-    };
-
-    let path = graf::a_star(astar_acc, start, end, heuristic).expect("Failed to find path");
+    let path = match any_angle {
+        Some((AnyAngle::Theta, grid)) => graf::theta_star(grid, start, end),
+        Some((AnyAngle::LazyTheta, grid)) => graf::lazy_theta_star(grid, start, end),
+        None => {
+            let heuristic = HeuristicDistance {
+                node2coord,
+                goal_pos: scenario.goal_pos,
+            };
+            graf::a_star(astar_acc, start, end, heuristic)
+        }
+    }?;
 
-    ScenarioResult { path }
+    Some(ScenarioResult { path })
 }
 
 fn parse_scenario_file(file: &Path) -> (Vec<SceneRecord>, String) {
@@ -233,6 +249,7 @@ fn run_for_scenario_file(
     maps: &Path,
     output_map: &Option<PathBuf>,
     output_graph: &Option<PathBuf>,
+    any_angle: Option<AnyAngle>,
 ) {
     let (scenarios, first_map) = parse_scenario_file(scenario);
     let mut path = std::path::PathBuf::from(maps);
@@ -269,18 +286,42 @@ fn run_for_scenario_file(
         dbg::dump_graph(&graph, &node2coord, o);
     }
 
-    let mut astar_acc = graf::AStarAcceleration::new(&graph);
+    let mut astar_acc = graf::AStarAcceleration::with_reachability(&graph);
+    let grid = Grid::new(raw_map.width(), raw_map.height(), |c| {
+        raw_map.is_traversable(c)
+    });
+    let any_angle = any_angle.map(|a| (a, &grid));
 
     println!("Scenario count: {}", scenarios.len());
     println!("Graph size: {}", graph.len());
     let pg = indicatif::ProgressBar::new(scenarios.len() as u64);
     for (scenario_idx, scenario) in scenarios.iter().enumerate() {
         pg.inc(1);
-        let result = run_single_scenario(scenario, &mut astar_acc, &coord2node, &node2coord);
+        let Some(result) = run_single_scenario(
+            scenario,
+            &mut astar_acc,
+            any_angle,
+            &coord2node,
+            &node2coord,
+        ) else {
+            pg.println(format!(
+                "[{}/{}] no path found. Start: {:?}, End: {:?}",
+                scenario_idx,
+                scenarios.len(),
+                scenario.start_pos,
+                scenario.goal_pos,
+            ));
+            continue;
+        };
         let cost = result.cost() as f64;
         let expected = scenario.optimal_length;
         let diff = (expected - cost).abs();
-        if diff > 0.001 {
+        let mismatch = if any_angle.is_some() {
+            cost - expected > 0.001
+        } else {
+            diff > 0.001
+        };
+        if mismatch {
             pg.println(format!(
                 "[{}/{}] shortest path mismatch. Start: {:?}, End: {:?}. Expected length {}, got {}, diff {}",
                 scenario_idx,
@@ -316,11 +357,23 @@ fn run(cli: Cli) {
                     p = path.path().display(),
                     m = cli.maps.display(),
                 );
-                run_for_scenario_file(&path.path(), &cli.maps, &cli.output_map, &cli.output_graph);
+                run_for_scenario_file(
+                    &path.path(),
+                    &cli.maps,
+                    &cli.output_map,
+                    &cli.output_graph,
+                    cli.any_angle,
+                );
             }
         }
     } else {
-        run_for_scenario_file(path, &cli.maps, &cli.output_map, &cli.output_graph);
+        run_for_scenario_file(
+            path,
+            &cli.maps,
+            &cli.output_map,
+            &cli.output_graph,
+            cli.any_angle,
+        );
     }
     println!("Took {} s to run", start.elapsed().as_secs_f32());
 }
@@ -0,0 +1,310 @@
+use crate::{undirected_edges, AdjacencyList, Node, NodeSet, Weight};
+
+use std::collections::VecDeque;
+
+/// The result of `hungarian`
+pub struct Assignment {
+    /// (left, right) pairs, one for each node in the left partition
+    pub pairs: Vec<(Node, Node)>,
+    pub cost: Weight,
+}
+
+/// The edges between `left` and the rest of the graph, as (left, right, weight). The graph is
+/// treated as undirected.
+fn crossing_edges(g: &AdjacencyList, left: &NodeSet) -> Vec<(Node, Node, Weight)> {
+    undirected_edges(g)
+        .into_iter()
+        .filter_map(|(a, b, w)| match (left.has(a), left.has(b)) {
+            (true, false) => Some((a, b, w)),
+            (false, true) => Some((b, a, w)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Maximum cardinality matching in a bipartite graph with the Hopcroft-Karp algorithm.
+///
+/// The graph is treated as undirected and `left` is one side of the partition, every other node
+/// is on the right side. Edges within a side are ignored. Returns matched (left, right) pairs.
+pub fn hopcroft_karp(g: &AdjacencyList, left: &NodeSet) -> Vec<(Node, Node)> {
+    const NONE: usize = usize::MAX;
+
+    let len = g.len();
+    let mut adj: Vec<Vec<Node>> = vec![Vec::new(); len];
+    for (a, b, _) in crossing_edges(g, left) {
+        adj[a.0].push(b);
+    }
+    let lefts: Vec<Node> = g.nodes().filter(|&n| left.has(n)).collect();
+
+    let mut mate: Vec<usize> = vec![NONE; len];
+    let mut dist: Vec<usize> = vec![NONE; len];
+    let mut next_edge: Vec<usize> = vec![0; len];
+    let mut queue = VecDeque::new();
+
+    loop {
+        // Layer the left nodes by alternating path length from the free ones
+        queue.clear();
+        for &u in &lefts {
+            if mate[u.0] == NONE {
+                dist[u.0] = 0;
+                queue.push_back(u);
+            } else {
+                dist[u.0] = NONE;
+            }
+        }
+        let mut free_dist = NONE;
+        while let Some(u) = queue.pop_front() {
+            if dist[u.0] >= free_dist {
+                continue;
+            }
+            for &v in &adj[u.0] {
+                let w = mate[v.0];
+                if w == NONE {
+                    free_dist = free_dist.min(dist[u.0] + 1);
+                } else if dist[w] == NONE {
+                    dist[w] = dist[u.0] + 1;
+                    queue.push_back(Node(w));
+                }
+            }
+        }
+        if free_dist == NONE {
+            break;
+        }
+
+        // Augment along vertex disjoint shortest paths
+        next_edge.iter_mut().for_each(|i| *i = 0);
+        for &u in &lefts {
+            if mate[u.0] != NONE {
+                continue;
+            }
+
+            let mut stack: Vec<Node> = vec![u];
+            let mut via: Vec<Node> = Vec::new();
+            while let Some(&x) = stack.last() {
+                let Some(&v) = adj[x.0].get(next_edge[x.0]) else {
+                    dist[x.0] = NONE;
+                    stack.pop();
+                    via.pop();
+                    continue;
+                };
+                next_edge[x.0] += 1;
+
+                let w = mate[v.0];
+                if w == NONE {
+                    if dist[x.0] + 1 != free_dist {
+                        continue;
+                    }
+                    via.push(v);
+                    for (l, r) in stack.iter().zip(&via) {
+                        mate[l.0] = r.0;
+                        mate[r.0] = l.0;
+                    }
+                    break;
+                } else if dist[w] == dist[x.0] + 1 {
+                    via.push(v);
+                    stack.push(Node(w));
+                }
+            }
+        }
+    }
+
+    lefts
+        .into_iter()
+        .filter(|u| mate[u.0] != NONE)
+        .map(|u| (u, Node(mate[u.0])))
+        .collect()
+}
+
+/// Minimum cost assignment with the Hungarian algorithm.
+///
+/// The graph is treated as undirected and `left` is one side of the partition, every other node
+/// is on the right side. Every left node is assigned a distinct right node so that the sum of
+/// the edge weights is minimized. Returns None if that is not possible, e.g. when the right side
+/// is smaller than the left.
+pub fn hungarian(g: &AdjacencyList, left: &NodeSet) -> Option<Assignment> {
+    let lefts: Vec<Node> = g.nodes().filter(|&n| left.has(n)).collect();
+    let rights: Vec<Node> = g.nodes().filter(|&n| !left.has(n)).collect();
+    let (rows, cols) = (lefts.len(), rights.len());
+    if rows == 0 {
+        return Some(Assignment {
+            pairs: Vec::new(),
+            cost: 0.0,
+        });
+    }
+    if rows > cols {
+        return None;
+    }
+
+    let mut row_of: Vec<usize> = vec![usize::MAX; g.len()];
+    let mut col_of: Vec<usize> = vec![usize::MAX; g.len()];
+    lefts.iter().enumerate().for_each(|(i, n)| row_of[n.0] = i);
+    rights.iter().enumerate().for_each(|(j, n)| col_of[n.0] = j);
+
+    // Missing edges get a cost larger than any complete assignment
+    let edges = crossing_edges(g, left);
+    let missing: f64 = 1.0 + edges.iter().map(|e| (e.2 as f64).abs()).sum::<f64>() * 2.0;
+    let mut cost: Vec<Vec<f64>> = vec![vec![missing; cols]; rows];
+    let mut exists: Vec<Vec<bool>> = vec![vec![false; cols]; rows];
+    for &(a, b, w) in &edges {
+        cost[row_of[a.0]][col_of[b.0]] = w as f64;
+        exists[row_of[a.0]][col_of[b.0]] = true;
+    }
+
+    // Shortest augmenting path formulation with row and column potentials, 1-indexed with column
+    // 0 as a virtual start.
+    let mut u = vec![0.0f64; rows + 1];
+    let mut v = vec![0.0f64; cols + 1];
+    let mut col_row = vec![0usize; cols + 1];
+    let mut way = vec![0usize; cols + 1];
+    for i in 1..=rows {
+        col_row[0] = i;
+        let mut j0 = 0;
+        let mut min_v = vec![f64::INFINITY; cols + 1];
+        let mut used = vec![false; cols + 1];
+        loop {
+            used[j0] = true;
+            let i0 = col_row[j0];
+            let mut delta = f64::INFINITY;
+            let mut j1 = 0;
+            for j in 1..=cols {
+                if used[j] {
+                    continue;
+                }
+                let cur = cost[i0 - 1][j - 1] - u[i0] - v[j];
+                if cur < min_v[j] {
+                    min_v[j] = cur;
+                    way[j] = j0;
+                }
+                if min_v[j] < delta {
+                    delta = min_v[j];
+                    j1 = j;
+                }
+            }
+            for j in 0..=cols {
+                if used[j] {
+                    u[col_row[j]] += delta;
+                    v[j] -= delta;
+                } else {
+                    min_v[j] -= delta;
+                }
+            }
+            j0 = j1;
+            if col_row[j0] == 0 {
+                break;
+            }
+        }
+        loop {
+            let j1 = way[j0];
+            col_row[j0] = col_row[j1];
+            j0 = j1;
+            if j0 == 0 {
+                break;
+            }
+        }
+    }
+
+    let mut pairs = Vec::with_capacity(rows);
+    let mut total = 0.0;
+    for j in 1..=cols {
+        let i = col_row[j];
+        if i == 0 {
+            continue;
+        }
+        if !exists[i - 1][j - 1] {
+            return None;
+        }
+        pairs.push((lefts[i - 1], rights[j - 1]));
+        total += cost[i - 1][j - 1];
+    }
+    pairs.sort();
+
+    Some(Assignment {
+        pairs,
+        cost: total as Weight,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{AdjacencyList, Node, NodeSet};
+
+    fn left(nodes: &[usize]) -> NodeSet {
+        let mut set = NodeSet::new();
+        for &n in nodes {
+            set.add(Node(n));
+        }
+        set
+    }
+
+    #[test]
+    fn hopcroft_karp() {
+        // Left 0..4, right 4..8. A greedy matching 0-4, 1-5 would block 2 and 3.
+        let mut g = AdjacencyList::new();
+        let n: Vec<Node> = (0..8).map(|_| g.add_node()).collect();
+        for (a, b) in [
+            (0, 4),
+            (0, 5),
+            (1, 4),
+            (1, 6),
+            (2, 5),
+            (3, 6),
+            (3, 7),
+            (7, 2),
+        ] {
+            g.add_edge(n[a], n[b], 1.0);
+        }
+        let matching = super::hopcroft_karp(&g, &left(&[0, 1, 2, 3]));
+        assert_eq!(matching.len(), 4);
+        let mut used = NodeSet::new();
+        for &(l, r) in &matching {
+            assert!(l.0 < 4 && r.0 >= 4);
+            assert!(g.has_edge(l, r) || g.has_edge(r, l));
+            assert!(used.add(r));
+        }
+    }
+
+    #[test]
+    fn hopcroft_karp_not_perfect() {
+        let mut g = AdjacencyList::new();
+        let n: Vec<Node> = (0..5).map(|_| g.add_node()).collect();
+        g.add_edge(n[0], n[3], 1.0);
+        g.add_edge(n[1], n[3], 1.0);
+        g.add_edge(n[2], n[4], 1.0);
+        g.add_edge(n[0], n[1], 1.0);
+        let matching = super::hopcroft_karp(&g, &left(&[0, 1, 2]));
+        assert_eq!(matching.len(), 2);
+    }
+
+    #[test]
+    fn hungarian() {
+        // Workers 0..3, jobs 3..6
+        let costs = [[4.0, 1.0, 3.0], [2.0, 0.0, 5.0], [3.0, 2.0, 2.0]];
+        let mut g = AdjacencyList::new();
+        let n: Vec<Node> = (0..6).map(|_| g.add_node()).collect();
+        for (i, row) in costs.iter().enumerate() {
+            for (j, &c) in row.iter().enumerate() {
+                g.add_edge(n[i], n[3 + j], c);
+            }
+        }
+        let result = super::hungarian(&g, &left(&[0, 1, 2])).unwrap();
+        assert_eq!(result.cost, 5.0);
+        assert_eq!(result.pairs, vec![(n[0], n[4]), (n[1], n[3]), (n[2], n[5])]);
+    }
+
+    #[test]
+    fn hungarian_rectangular_and_infeasible() {
+        let mut g = AdjacencyList::new();
+        let n: Vec<Node> = (0..5).map(|_| g.add_node()).collect();
+        g.add_edge(n[0], n[2], 5.0);
+        g.add_edge(n[0], n[3], 1.0);
+        g.add_edge(n[1], n[3], 2.0);
+        g.add_edge(n[1], n[4], 4.0);
+        let result = super::hungarian(&g, &left(&[0, 1])).unwrap();
+        assert_eq!(result.cost, 5.0);
+        assert_eq!(result.pairs, vec![(n[0], n[3]), (n[1], n[4])]);
+
+        g.remove_edge(n[1], n[4]);
+        g.remove_edge(n[0], n[2]);
+        assert!(super::hungarian(&g, &left(&[0, 1])).is_none());
+    }
+}
@@ -0,0 +1,968 @@
+use crate::{undirected_edges, AdjacencyList, Node, NodeMap, Weight};
+
+use std::collections::VecDeque;
+
+const NONE: usize = usize::MAX;
+
+fn to_mates(mate: &[usize]) -> NodeMap<Node> {
+    let mut out = NodeMap::with_capacity(mate.len());
+    for (i, &m) in mate.iter().enumerate() {
+        if m != NONE {
+            out.insert(Node(i), Node(m));
+        }
+    }
+    out
+}
+
+/// Maximum cardinality matching in a general graph with Edmonds' blossom algorithm.
+///
+/// The graph is treated as undirected. Every matched node maps to its mate, in both directions.
+pub fn maximum_matching(g: &AdjacencyList) -> NodeMap<Node> {
+    let len = g.len();
+    let mut adj: Vec<Vec<usize>> = vec![Vec::new(); len];
+    for (a, b, _) in undirected_edges(g) {
+        adj[a.0].push(b.0);
+        adj[b.0].push(a.0);
+    }
+
+    let mut mate = vec![NONE; len];
+    // Greedy start, the search only has to fix what this gets wrong
+    for v in 0..len {
+        if mate[v] == NONE {
+            if let Some(&w) = adj[v].iter().find(|&&w| mate[w] == NONE) {
+                mate[v] = w;
+                mate[w] = v;
+            }
+        }
+    }
+
+    let mut parent = vec![NONE; len];
+    let mut base: Vec<usize> = (0..len).collect();
+    let mut used = vec![false; len];
+    let mut in_blossom = vec![false; len];
+    let mut on_path = vec![false; len];
+    let mut queue = VecDeque::new();
+
+    for root in 0..len {
+        if mate[root] != NONE {
+            continue;
+        }
+
+        parent.iter_mut().for_each(|p| *p = NONE);
+        used.iter_mut().for_each(|u| *u = false);
+        base.iter_mut().enumerate().for_each(|(i, b)| *b = i);
+        queue.clear();
+        used[root] = true;
+        queue.push_back(root);
+
+        let mut found = NONE;
+        'search: while let Some(v) = queue.pop_front() {
+            for &to in &adj[v] {
+                if base[v] == base[to] || mate[v] == to {
+                    continue;
+                }
+
+                if to == root || (mate[to] != NONE && parent[mate[to]] != NONE) {
+                    // Odd cycle, contract the blossom into its base
+                    on_path.iter_mut().for_each(|x| *x = false);
+                    let mut a = v;
+                    loop {
+                        a = base[a];
+                        on_path[a] = true;
+                        if mate[a] == NONE {
+                            break;
+                        }
+                        a = parent[mate[a]];
+                    }
+                    let mut b = to;
+                    let lca = loop {
+                        b = base[b];
+                        if on_path[b] {
+                            break b;
+                        }
+                        b = parent[mate[b]];
+                    };
+
+                    in_blossom.iter_mut().for_each(|x| *x = false);
+                    for (start, child) in [(v, to), (to, v)] {
+                        let (mut x, mut child) = (start, child);
+                        while base[x] != lca {
+                            in_blossom[base[x]] = true;
+                            in_blossom[base[mate[x]]] = true;
+                            parent[x] = child;
+                            child = mate[x];
+                            x = parent[mate[x]];
+                        }
+                    }
+                    for i in 0..len {
+                        if in_blossom[base[i]] {
+                            base[i] = lca;
+                            if !used[i] {
+                                used[i] = true;
+                                queue.push_back(i);
+                            }
+                        }
+                    }
+                } else if parent[to] == NONE {
+                    parent[to] = v;
+                    if mate[to] == NONE {
+                        found = to;
+                        break 'search;
+                    }
+                    used[mate[to]] = true;
+                    queue.push_back(mate[to]);
+                }
+            }
+        }
+
+        // Flip the augmenting path
+        let mut v = found;
+        while v != NONE {
+            let pv = parent[v];
+            let next = mate[pv];
+            mate[v] = pv;
+            mate[pv] = v;
+            v = next;
+        }
+    }
+
+    to_mates(&mate)
+}
+
+/// Tree search label of a vertex or top-level blossom in the weighted matching
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Label {
+    Free,
+    /// S, at an even distance from a free vertex in the alternating forest
+    Even,
+    /// T, at an odd distance, reached through an edge that is not in the matching
+    Odd,
+}
+
+/// How the duals are changed when the search is stuck, and what that allows next
+enum Delta {
+    /// The matching is optimal, or of maximum cardinality with optimal duals
+    Done,
+    /// The edge from an Even vertex to a free vertex becomes tight
+    ToFree(usize),
+    /// The edge between two Even blossoms becomes tight
+    BetweenEven(usize),
+    /// The dual of the Odd blossom reaches zero and it can be expanded
+    ExpandOdd(usize),
+}
+
+/// Primal-dual state for `max_weight_matching`, following Galil's O(n^3) formulation and the
+/// reference implementation by Van Rantwijk.
+///
+/// Vertices are 0..len and blossoms len..2 * len. A blossom id is in use while its `base` is set.
+///
+/// Edge k has the endpoints 2k and 2k + 1, at its first and second node. So `p ^ 1` is the other
+/// end of the edge at endpoint p and `p / 2` is the edge itself. `mate` and `label_endpoint`
+/// hold endpoints rather than nodes so that they also tell which edge is used.
+///
+/// Invariants between stages:
+/// - Vertex duals are doubled, the slack of edge (i, j) is `dual[i] + dual[j] - 2 w`. Blossom
+///   duals are left out since edges inside a blossom are never scanned. No edge between
+///   different top-level blossoms has negative slack, and matched edges have zero slack.
+/// - Blossom duals are never negative. A blossom is expanded when its dual reaches zero, as an
+///   Odd blossom during the search or as an Even one at the end of a stage.
+/// - `children[b]` is the odd cycle of sub-blossoms, starting with the one that holds the base.
+///   `endpoints[b][i]` is the endpoint in `children[b][i]` of the edge to the next child.
+///
+/// During a stage, free vertices are the Even roots of an alternating forest. An Odd blossom is
+/// entered through `label_endpoint`, the far endpoint of the edge from its Even parent. An Even
+/// blossom that is not a root is entered through its matched edge.
+struct Weighted {
+    len: usize,
+    edges: Vec<(usize, usize, f64)>,
+    /// The node at each endpoint
+    node_at: Vec<usize>,
+    /// For each vertex, the far endpoints of its edges
+    neighbor_endpoints: Vec<Vec<usize>>,
+    /// For each vertex, the endpoint at its mate, NONE if unmatched
+    mate: Vec<usize>,
+    label: Vec<Label>,
+    label_endpoint: Vec<usize>,
+    /// The top-level blossom of each vertex, the vertex itself if it is in no blossom
+    top_blossom: Vec<usize>,
+    parent: Vec<usize>,
+    children: Vec<Vec<usize>>,
+    base: Vec<usize>,
+    endpoints: Vec<Vec<usize>>,
+    /// For a free vertex or an Even top-level blossom, the edge with the least slack to another
+    /// Even blossom
+    best_edge: Vec<usize>,
+    /// For an Even blossom, the least-slack edge to each neighboring Even blossom
+    best_edges: Vec<Option<Vec<usize>>>,
+    unused: Vec<usize>,
+    dual: Vec<f64>,
+    /// Edges known to have zero slack in this stage
+    allowed: Vec<bool>,
+    /// Even vertices whose edges are not scanned yet
+    queue: Vec<usize>,
+    /// Blossoms passed by the current `scan_blossom`
+    marked: Vec<bool>,
+}
+
+impl Weighted {
+    fn new(len: usize, edges: Vec<(usize, usize, f64)>) -> Self {
+        let max_weight = edges.iter().map(|e| e.2).fold(0.0, f64::max);
+        let mut node_at = Vec::with_capacity(2 * edges.len());
+        let mut neighbor_endpoints = vec![Vec::new(); len];
+        for (k, &(i, j, _)) in edges.iter().enumerate() {
+            node_at.push(i);
+            node_at.push(j);
+            neighbor_endpoints[i].push(2 * k + 1);
+            neighbor_endpoints[j].push(2 * k);
+        }
+        let mut base: Vec<usize> = (0..len).collect();
+        base.resize(2 * len, NONE);
+        let mut dual = vec![max_weight; len];
+        dual.resize(2 * len, 0.0);
+        Self {
+            len,
+            allowed: vec![false; edges.len()],
+            edges,
+            node_at,
+            neighbor_endpoints,
+            mate: vec![NONE; len],
+            label: vec![Label::Free; 2 * len],
+            label_endpoint: vec![NONE; 2 * len],
+            top_blossom: (0..len).collect(),
+            parent: vec![NONE; 2 * len],
+            children: vec![Vec::new(); 2 * len],
+            base,
+            endpoints: vec![Vec::new(); 2 * len],
+            best_edge: vec![NONE; 2 * len],
+            best_edges: vec![None; 2 * len],
+            unused: (len..2 * len).collect(),
+            dual,
+            queue: Vec::new(),
+            marked: vec![false; 2 * len],
+        }
+    }
+
+    fn slack(&self, k: usize) -> f64 {
+        let (i, j, w) = self.edges[k];
+        self.dual[i] + self.dual[j] - 2.0 * w
+    }
+
+    /// The vertices in blossom b
+    fn leaves(&self, b: usize) -> Vec<usize> {
+        let mut out = Vec::new();
+        let mut stack = vec![b];
+        while let Some(t) = stack.pop() {
+            if t < self.len {
+                out.push(t);
+            } else {
+                stack.extend(self.children[t].iter().rev());
+            }
+        }
+        out
+    }
+
+    /// Label the top-level blossom of w, entered through endpoint p. An Odd blossom passes the
+    /// search on to its mate, which becomes Even.
+    fn assign_label(&mut self, w: usize, label: Label, p: usize) {
+        let b = self.top_blossom[w];
+        self.label[w] = label;
+        self.label[b] = label;
+        self.label_endpoint[w] = p;
+        self.label_endpoint[b] = p;
+        self.best_edge[w] = NONE;
+        self.best_edge[b] = NONE;
+        match label {
+            Label::Even => {
+                let leaves = self.leaves(b);
+                self.queue.extend(leaves);
+            }
+            Label::Odd => {
+                let m = self.mate[self.base[b]];
+                self.assign_label(self.node_at[m], Label::Even, m ^ 1);
+            }
+            Label::Free => {}
+        }
+    }
+
+    /// Trace back from the Even vertices v and w toward their roots to find a new blossom or an
+    /// augmenting path. Returns the base of the blossom or NONE for an augmenting path.
+    fn scan_blossom(&mut self, mut v: usize, mut w: usize) -> usize {
+        let mut path = Vec::new();
+        let mut base = NONE;
+        while v != NONE || w != NONE {
+            let mut b = self.top_blossom[v];
+            if self.marked[b] {
+                base = self.base[b];
+                break;
+            }
+            path.push(b);
+            self.marked[b] = true;
+            if self.label_endpoint[b] == NONE {
+                v = NONE;
+            } else {
+                // Step over the Odd blossom to the next Even one
+                v = self.node_at[self.label_endpoint[b]];
+                b = self.top_blossom[v];
+                v = self.node_at[self.label_endpoint[b]];
+            }
+            if w != NONE {
+                std::mem::swap(&mut v, &mut w);
+            }
+        }
+        for b in path {
+            self.marked[b] = false;
+        }
+        base
+    }
+
+    /// Contract the cycle closed by edge k through `base` into a new Even blossom
+    fn add_blossom(&mut self, base: usize, k: usize) {
+        let (mut v, mut w, _) = self.edges[k];
+        let bb = self.top_blossom[base];
+        let mut bv = self.top_blossom[v];
+        let mut bw = self.top_blossom[w];
+        let b = self.unused.pop().expect("Out of blossom ids");
+        self.base[b] = base;
+        self.parent[b] = NONE;
+        self.parent[bb] = b;
+
+        let mut children = Vec::new();
+        let mut endpoints = Vec::new();
+        while bv != bb {
+            self.parent[bv] = b;
+            children.push(bv);
+            endpoints.push(self.label_endpoint[bv]);
+            v = self.node_at[self.label_endpoint[bv]];
+            bv = self.top_blossom[v];
+        }
+        children.push(bb);
+        children.reverse();
+        endpoints.reverse();
+        endpoints.push(2 * k);
+        while bw != bb {
+            self.parent[bw] = b;
+            children.push(bw);
+            endpoints.push(self.label_endpoint[bw] ^ 1);
+            w = self.node_at[self.label_endpoint[bw]];
+            bw = self.top_blossom[w];
+        }
+
+        self.label[b] = Label::Even;
+        self.label_endpoint[b] = self.label_endpoint[bb];
+        self.dual[b] = 0.0;
+        self.children[b] = children.clone();
+        self.endpoints[b] = endpoints;
+        for leaf in self.leaves(b) {
+            // Former Odd vertices are Even now and need to be scanned
+            if self.label[self.top_blossom[leaf]] == Label::Odd {
+                self.queue.push(leaf);
+            }
+            self.top_blossom[leaf] = b;
+        }
+        self.merge_best_edges(b, &children);
+    }
+
+    /// Collect the least-slack edges from the new blossom b to each neighboring Even blossom
+    fn merge_best_edges(&mut self, b: usize, children: &[usize]) {
+        let mut best_to = vec![NONE; 2 * self.len];
+        for &child in children {
+            let lists: Vec<Vec<usize>> = match self.best_edges[child].take() {
+                Some(list) => vec![list],
+                None => self
+                    .leaves(child)
+                    .into_iter()
+                    .map(|v| self.neighbor_endpoints[v].iter().map(|p| p / 2).collect())
+                    .collect(),
+            };
+            for k in lists.into_iter().flatten() {
+                let (i, j, _) = self.edges[k];
+                let j = if self.top_blossom[j] == b { i } else { j };
+                let bj = self.top_blossom[j];
+                if bj != b
+                    && self.label[bj] == Label::Even
+                    && (best_to[bj] == NONE || self.slack(k) < self.slack(best_to[bj]))
+                {
+                    best_to[bj] = k;
+                }
+            }
+            self.best_edge[child] = NONE;
+        }
+
+        let best: Vec<usize> = best_to.into_iter().filter(|&k| k != NONE).collect();
+        self.best_edge[b] = NONE;
+        for &k in &best {
+            if self.best_edge[b] == NONE || self.slack(k) < self.slack(self.best_edge[b]) {
+                self.best_edge[b] = k;
+            }
+        }
+        self.best_edges[b] = Some(best);
+    }
+
+    /// Index j of the children of b, wrapping around the cycle
+    fn child_index(&self, b: usize, j: isize) -> usize {
+        j.rem_euclid(self.children[b].len() as isize) as usize
+    }
+
+    /// How to walk from child i of b to the base child 0 along the even length side of the
+    /// cycle. Returns the start index, the step and `flip`. Walking backward, the edge to the
+    /// next child is stored at that child, so its endpoint is taken at index j - 1 and flipped
+    /// with `^ 1` to get the near end.
+    fn even_side(&self, b: usize, i: usize) -> (isize, isize, usize) {
+        let i = i as isize;
+        if i & 1 != 0 {
+            (i - self.children[b].len() as isize, 1, 0)
+        } else {
+            (i, -1, 1)
+        }
+    }
+
+    /// The endpoint of the edge from child j toward the next child in the walk direction, seen
+    /// from child j
+    fn walk_endpoint(&self, b: usize, j: isize, flip: usize) -> usize {
+        self.endpoints[b][self.child_index(b, j - flip as isize)] ^ flip
+    }
+
+    /// Turn the sub-blossoms of b into top-level blossoms. At the end of a stage, sub-blossoms
+    /// with zero dual are expanded too. An Odd blossom expanded during the search has its
+    /// children relabeled so that the forest stays alternating.
+    fn expand_blossom(&mut self, b: usize, end_stage: bool) {
+        for s in self.children[b].clone() {
+            self.parent[s] = NONE;
+            if s < self.len {
+                self.top_blossom[s] = s;
+            } else if end_stage && self.dual[s] == 0.0 {
+                self.expand_blossom(s, end_stage);
+            } else {
+                for v in self.leaves(s) {
+                    self.top_blossom[v] = s;
+                }
+            }
+        }
+
+        if !end_stage && self.label[b] == Label::Odd {
+            self.relabel_expanded(b);
+        }
+
+        self.label[b] = Label::Free;
+        self.label_endpoint[b] = NONE;
+        self.children[b].clear();
+        self.endpoints[b].clear();
+        self.base[b] = NONE;
+        self.best_edges[b] = None;
+        self.best_edge[b] = NONE;
+        self.unused.push(b);
+    }
+
+    /// After expanding the Odd blossom b, label its children on the even length path from the
+    /// entry child to the base alternately Odd and Even. The children on the other side are
+    /// left free unless they are reachable from the forest through one of their vertices.
+    fn relabel_expanded(&mut self, b: usize) {
+        let entry_child = self.top_blossom[self.node_at[self.label_endpoint[b] ^ 1]];
+        let i = self.children[b]
+            .iter()
+            .position(|&c| c == entry_child)
+            .expect("Entry child in blossom");
+        let (mut j, step, flip) = self.even_side(b, i);
+
+        let mut p = self.label_endpoint[b];
+        while j != 0 {
+            self.label[self.node_at[p ^ 1]] = Label::Free;
+            let q = self.walk_endpoint(b, j, flip) ^ 1;
+            self.label[self.node_at[q]] = Label::Free;
+            self.assign_label(self.node_at[p ^ 1], Label::Odd, p);
+            let forward = self.walk_endpoint(b, j, flip) ^ flip;
+            self.allowed[forward / 2] = true;
+            j += step;
+            p = self.walk_endpoint(b, j, flip);
+            self.allowed[p / 2] = true;
+            j += step;
+        }
+
+        // The base child is entered through p, without relabeling its mate
+        let bv = self.children[b][self.child_index(b, j)];
+        self.label[self.node_at[p ^ 1]] = Label::Odd;
+        self.label[bv] = Label::Odd;
+        self.label_endpoint[self.node_at[p ^ 1]] = p;
+        self.label_endpoint[bv] = p;
+        self.best_edge[bv] = NONE;
+
+        j += step;
+        while self.children[b][self.child_index(b, j)] != entry_child {
+            let bv = self.children[b][self.child_index(b, j)];
+            j += step;
+            if self.label[bv] == Label::Even {
+                continue;
+            }
+            let reached = self
+                .leaves(bv)
+                .into_iter()
+                .find(|&v| self.label[v] != Label::Free);
+            if let Some(v) = reached {
+                self.label[v] = Label::Free;
+                let m = self.mate[self.base[bv]];
+                self.label[self.node_at[m]] = Label::Free;
+                self.assign_label(v, Label::Odd, self.label_endpoint[v]);
+            }
+        }
+    }
+
+    /// Swap matched and unmatched edges in blossom b so that v becomes its base
+    fn augment_blossom(&mut self, b: usize, v: usize) {
+        let mut t = v;
+        while self.parent[t] != b {
+            t = self.parent[t];
+        }
+        if t >= self.len {
+            self.augment_blossom(t, v);
+        }
+
+        let i = self.children[b]
+            .iter()
+            .position(|&c| c == t)
+            .expect("Child in blossom");
+        let (mut j, step, flip) = self.even_side(b, i);
+        while j != 0 {
+            j += step;
+            let t = self.children[b][self.child_index(b, j)];
+            let p = self.walk_endpoint(b, j, flip);
+            if t >= self.len {
+                self.augment_blossom(t, self.node_at[p]);
+            }
+            j += step;
+            let t = self.children[b][self.child_index(b, j)];
+            if t >= self.len {
+                self.augment_blossom(t, self.node_at[p ^ 1]);
+            }
+            self.mate[self.node_at[p]] = p ^ 1;
+            self.mate[self.node_at[p ^ 1]] = p;
+        }
+
+        self.children[b].rotate_left(i);
+        self.endpoints[b].rotate_left(i);
+        self.base[b] = self.base[self.children[b][0]];
+    }
+
+    /// Flip the matching along the augmenting path through edge k, from both ends to the roots
+    fn augment(&mut self, k: usize) {
+        let (v, w, _) = self.edges[k];
+        for (mut s, mut p) in [(v, 2 * k + 1), (w, 2 * k)] {
+            loop {
+                let bs = self.top_blossom[s];
+                if bs >= self.len {
+                    self.augment_blossom(bs, s);
+                }
+                self.mate[s] = p;
+                if self.label_endpoint[bs] == NONE {
+                    break;
+                }
+                let t = self.node_at[self.label_endpoint[bs]];
+                let bt = self.top_blossom[t];
+                s = self.node_at[self.label_endpoint[bt]];
+                let j = self.node_at[self.label_endpoint[bt] ^ 1];
+                if bt >= self.len {
+                    self.augment_blossom(bt, j);
+                }
+                self.mate[j] = self.label_endpoint[bt];
+                p = self.label_endpoint[bt] ^ 1;
+            }
+        }
+    }
+
+    /// Clear the forest and make every free vertex an Even root
+    fn start_stage(&mut self) {
+        self.label.iter_mut().for_each(|l| *l = Label::Free);
+        self.best_edge.iter_mut().for_each(|e| *e = NONE);
+        self.best_edges[self.len..]
+            .iter_mut()
+            .for_each(|e| *e = None);
+        self.allowed.iter_mut().for_each(|a| *a = false);
+        self.queue.clear();
+
+        for v in 0..self.len {
+            if self.mate[v] == NONE && self.label[self.top_blossom[v]] == Label::Free {
+                self.assign_label(v, Label::Even, NONE);
+            }
+        }
+    }
+
+    /// Scan the edges of the queued Even vertices over tight edges, growing the forest and
+    /// forming blossoms. Returns true if the matching was augmented.
+    fn grow(&mut self) -> bool {
+        while let Some(v) = self.queue.pop() {
+            for pi in 0..self.neighbor_endpoints[v].len() {
+                let p = self.neighbor_endpoints[v][pi];
+                let k = p / 2;
+                let w = self.node_at[p];
+                if self.top_blossom[v] == self.top_blossom[w] {
+                    continue;
+                }
+                let mut slack = 0.0;
+                if !self.allowed[k] {
+                    slack = self.slack(k);
+                    if slack <= 0.0 {
+                        self.allowed[k] = true;
+                    }
+                }
+
+                let bw = self.top_blossom[w];
+                if self.allowed[k] {
+                    match self.label[bw] {
+                        Label::Free => self.assign_label(w, Label::Odd, p ^ 1),
+                        Label::Even => {
+                            let base = self.scan_blossom(v, w);
+                            if base == NONE {
+                                self.augment(k);
+                                return true;
+                            }
+                            self.add_blossom(base, k);
+                        }
+                        Label::Odd if self.label[w] == Label::Free => {
+                            // Remember how w was reached in case its blossom is expanded
+                            self.label[w] = Label::Odd;
+                            self.label_endpoint[w] = p ^ 1;
+                        }
+                        Label::Odd => {}
+                    }
+                } else if self.label[bw] == Label::Even {
+                    let b = self.top_blossom[v];
+                    if self.best_edge[b] == NONE || slack < self.slack(self.best_edge[b]) {
+                        self.best_edge[b] = k;
+                    }
+                } else if self.label[w] == Label::Free
+                    && (self.best_edge[w] == NONE || slack < self.slack(self.best_edge[w]))
+                {
+                    self.best_edge[w] = k;
+                }
+            }
+        }
+        false
+    }
+
+    /// The largest dual change that keeps every slack and blossom dual non-negative, and what
+    /// becomes possible after it
+    fn choose_delta(&self, max_cardinality: bool) -> (f64, Delta) {
+        let min_vertex_dual = || {
+            self.dual[..self.len]
+                .iter()
+                .copied()
+                .fold(f64::INFINITY, f64::min)
+        };
+        let mut best = (!max_cardinality).then(|| (min_vertex_dual(), Delta::Done));
+        let mut consider = |d: f64, kind: Delta| {
+            if best.as_ref().is_none_or(|(delta, _)| d < *delta) {
+                best = Some((d, kind));
+            }
+        };
+
+        for v in 0..self.len {
+            if self.label[self.top_blossom[v]] == Label::Free && self.best_edge[v] != NONE {
+                consider(
+                    self.slack(self.best_edge[v]),
+                    Delta::ToFree(self.best_edge[v]),
+                );
+            }
+        }
+        for b in 0..2 * self.len {
+            if self.parent[b] == NONE && self.label[b] == Label::Even && self.best_edge[b] != NONE {
+                let k = self.best_edge[b];
+                consider(self.slack(k) / 2.0, Delta::BetweenEven(k));
+            }
+        }
+        for b in self.len..2 * self.len {
+            if self.base[b] != NONE && self.parent[b] == NONE && self.label[b] == Label::Odd {
+                consider(self.dual[b], Delta::ExpandOdd(b));
+            }
+        }
+
+        // Maximum cardinality reached, finish with an optimum dual solution
+        best.unwrap_or_else(|| (min_vertex_dual().max(0.0), Delta::Done))
+    }
+
+    fn apply_delta(&mut self, delta: f64) {
+        for v in 0..self.len {
+            match self.label[self.top_blossom[v]] {
+                Label::Even => self.dual[v] -= delta,
+                Label::Odd => self.dual[v] += delta,
+                Label::Free => {}
+            }
+        }
+        for b in self.len..2 * self.len {
+            if self.base[b] != NONE && self.parent[b] == NONE {
+                match self.label[b] {
+                    Label::Even => self.dual[b] += delta,
+                    Label::Odd => self.dual[b] -= delta,
+                    Label::Free => {}
+                }
+            }
+        }
+    }
+
+    /// One stage, search for an augmenting path and update the duals whenever the search is
+    /// stuck. Returns false if there is no augmenting path left.
+    fn stage(&mut self, max_cardinality: bool) -> bool {
+        self.start_stage();
+        loop {
+            if self.grow() {
+                break;
+            }
+            let (delta, kind) = self.choose_delta(max_cardinality);
+            self.apply_delta(delta);
+            match kind {
+                Delta::Done => return false,
+                Delta::ToFree(k) => {
+                    self.allowed[k] = true;
+                    let (i, j, _) = self.edges[k];
+                    let even = if self.label[self.top_blossom[i]] == Label::Free {
+                        j
+                    } else {
+                        i
+                    };
+                    self.queue.push(even);
+                }
+                Delta::BetweenEven(k) => {
+                    self.allowed[k] = true;
+                    self.queue.push(self.edges[k].0);
+                }
+                Delta::ExpandOdd(b) => self.expand_blossom(b, false),
+            }
+        }
+
+        // Even blossoms with zero dual don't need to stay contracted
+        for b in self.len..2 * self.len {
+            if self.parent[b] == NONE
+                && self.base[b] != NONE
+                && self.label[b] == Label::Even
+                && self.dual[b] == 0.0
+            {
+                self.expand_blossom(b, true);
+            }
+        }
+        true
+    }
+
+    fn solve(&mut self, max_cardinality: bool) {
+        // Every stage but the last adds an edge to the matching
+        for _ in 0..self.len {
+            if !self.stage(max_cardinality) {
+                break;
+            }
+        }
+    }
+}
+
+/// Maximum weight matching in a general graph with Edmonds' blossom algorithm, using a primal-dual
+/// method in O(n^3).
+///
+/// The graph is treated as undirected. If `max_cardinality` is set, only matchings with the
+/// maximum number of edges are considered. Every matched node maps to its mate, in both
+/// directions.
+pub fn max_weight_matching(g: &AdjacencyList, max_cardinality: bool) -> NodeMap<Node> {
+    let edges: Vec<(usize, usize, f64)> = undirected_edges(g)
+        .into_iter()
+        .map(|(a, b, w)| (a.0, b.0, w as f64))
+        .collect();
+    if edges.is_empty() {
+        return NodeMap::new();
+    }
+
+    let mut state = Weighted::new(g.len(), edges);
+    state.solve(max_cardinality);
+    let mate: Vec<usize> = state
+        .mate
+        .iter()
+        .map(|&p| if p == NONE { NONE } else { state.node_at[p] })
+        .collect();
+    to_mates(&mate)
+}
+
+/// The total weight of the edges in a matching from `max_weight_matching` or `maximum_matching`
+pub fn matching_weight(g: &AdjacencyList, mates: &NodeMap<Node>) -> Weight {
+    let edges = undirected_edges(g);
+    mates
+        .iter()
+        .filter(|(a, b)| a < *b)
+        .map(|(a, &b)| {
+            let i = edges
+                .binary_search_by(|e| (e.0, e.1).cmp(&(a, b)))
+                .expect("Matched edge in graph");
+            edges[i].2
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{AdjacencyList, Node, NodeMap};
+
+    fn graph(len: usize, edges: &[(usize, usize, f32)]) -> AdjacencyList {
+        let mut g = AdjacencyList::new();
+        for _ in 0..len {
+            g.add_node();
+        }
+        for &(a, b, w) in edges {
+            g.add_edge(Node(a), Node(b), w);
+        }
+        g
+    }
+
+    fn check_valid(g: &AdjacencyList, mates: &NodeMap<Node>) {
+        for (a, &b) in mates.iter() {
+            assert_eq!(mates[b], a);
+            assert!(g.has_edge(a, b) || g.has_edge(b, a));
+        }
+    }
+
+    fn mates_of(mates: &NodeMap<Node>, len: usize) -> Vec<Option<usize>> {
+        (0..len).map(|i| mates.get(&Node(i)).map(|n| n.0)).collect()
+    }
+
+    #[test]
+    fn cardinality_needs_blossom() {
+        // A 5-cycle 0..5 with a tail 4-5 and 0-6, greedy pairs (0,1), (2,3) leave 4, 5, 6 badly
+        let g = graph(
+            7,
+            &[
+                (0, 1, 1.0),
+                (1, 2, 1.0),
+                (2, 3, 1.0),
+                (3, 4, 1.0),
+                (4, 0, 1.0),
+                (4, 5, 1.0),
+                (0, 6, 1.0),
+            ],
+        );
+        let mates = super::maximum_matching(&g);
+        check_valid(&g, &mates);
+        assert_eq!(mates.iter().count(), 6);
+    }
+
+    #[test]
+    fn cardinality_odd() {
+        let g = graph(3, &[(0, 1, 1.0), (1, 2, 1.0), (2, 0, 1.0)]);
+        let mates = super::maximum_matching(&g);
+        check_valid(&g, &mates);
+        assert_eq!(mates.iter().count(), 2);
+    }
+
+    #[test]
+    fn weighted_simple() {
+        let g = graph(4, &[(0, 1, 1.0), (1, 2, 3.0), (2, 3, 1.0)]);
+        let mates = super::max_weight_matching(&g, false);
+        assert_eq!(mates_of(&mates, 4), vec![None, Some(2), Some(1), None]);
+        assert_eq!(super::matching_weight(&g, &mates), 3.0);
+
+        let mates = super::max_weight_matching(&g, true);
+        assert_eq!(
+            mates_of(&mates, 4),
+            vec![Some(1), Some(0), Some(3), Some(2)]
+        );
+    }
+
+    #[test]
+    fn weighted_blossom() {
+        // Test cases from the reference implementation's test suite
+        let g = graph(5, &[(1, 2, 8.0), (1, 3, 9.0), (2, 3, 10.0), (3, 4, 7.0)]);
+        let mates = super::max_weight_matching(&g, false);
+        assert_eq!(
+            mates_of(&mates, 5),
+            vec![None, Some(2), Some(1), Some(4), Some(3)]
+        );
+
+        let g = graph(
+            7,
+            &[
+                (1, 2, 9.0),
+                (1, 3, 8.0),
+                (2, 3, 10.0),
+                (1, 4, 5.0),
+                (4, 5, 4.0),
+                (1, 6, 3.0),
+            ],
+        );
+        let mates = super::max_weight_matching(&g, false);
+        assert_eq!(
+            mates_of(&mates, 7),
+            vec![None, Some(6), Some(3), Some(2), Some(5), Some(4), Some(1)]
+        );
+
+        // Nested S-blossom, relabeled as T-blossom and expanded
+        let g = graph(
+            9,
+            &[
+                (1, 2, 23.0),
+                (1, 5, 22.0),
+                (1, 6, 15.0),
+                (2, 3, 25.0),
+                (3, 4, 22.0),
+                (4, 5, 25.0),
+                (4, 8, 14.0),
+                (5, 7, 13.0),
+            ],
+        );
+        let mates = super::max_weight_matching(&g, false);
+        assert_eq!(
+            mates_of(&mates, 9),
+            vec![
+                None,
+                Some(6),
+                Some(3),
+                Some(2),
+                Some(8),
+                Some(7),
+                Some(1),
+                Some(5),
+                Some(4)
+            ]
+        );
+    }
+
+    #[test]
+    fn weighted_matches_brute_force() {
+        let mut rand = crate::test_rng(0x9e3779b97f4a7c15);
+
+        fn best(edges: &[(usize, usize, f32)], used: u32, from: usize, card: bool) -> (usize, f32) {
+            let mut result = (0, 0.0);
+            for (i, &(a, b, w)) in edges.iter().enumerate().skip(from) {
+                if used & (1 << a) != 0 || used & (1 << b) != 0 {
+                    continue;
+                }
+                let (c, s) = best(edges, used | 1 << a | 1 << b, i + 1, card);
+                let candidate = (c + 1, s + w);
+                let better = if card {
+                    candidate.0 > result.0 || (candidate.0 == result.0 && candidate.1 > result.1)
+                } else {
+                    candidate.1 > result.1
+                };
+                if better {
+                    result = candidate;
+                }
+            }
+            result
+        }
+
+        for round in 0..40 {
+            let len = 8;
+            let mut edges = Vec::new();
+            for _ in 0..12 {
+                let (a, b) = (rand(len) as usize, rand(len) as usize);
+                if a != b {
+                    edges.push((a.min(b), a.max(b), rand(20) as f32));
+                }
+            }
+            let g = graph(len as usize, &edges);
+            let edges: Vec<(usize, usize, f32)> = crate::undirected_edges(&g)
+                .into_iter()
+                .map(|(a, b, w)| (a.0, b.0, w))
+                .collect();
+
+            let card = round % 2 == 0;
+            let mates = super::max_weight_matching(&g, card);
+            check_valid(&g, &mates);
+            let (count, weight) = best(&edges, 0, 0, card);
+            assert_eq!(super::matching_weight(&g, &mates), weight, "round {round}");
+            if card {
+                assert_eq!(mates.iter().count(), 2 * count, "round {round}");
+            }
+        }
+    }
+}
@@ -0,0 +1,409 @@
+use crate::{edge, AdjacencyList, Node, NodeMap, Weight};
+
+use std::collections::{BinaryHeap, VecDeque};
+
+/// Run `visit` for every node as a source on `threads` threads, at least one. Each thread adds to
+/// its own scores, which are summed at the end.
+fn per_source(
+    g: &AdjacencyList,
+    threads: usize,
+    visit: impl Fn(Node, &mut Search, &mut [f64]) + Sync,
+) -> NodeMap<f64> {
+    let len = g.len();
+    let chunk = len.div_ceil(threads.max(1)).max(1);
+    let sources: Vec<Node> = g.nodes().collect();
+    let partial: Vec<Vec<f64>> = std::thread::scope(|s| {
+        let workers: Vec<_> = sources
+            .chunks(chunk)
+            .map(|chunk| {
+                let visit = &visit;
+                s.spawn(move || {
+                    let mut search = Search::new(len);
+                    let mut scores = vec![0.0; len];
+                    for &n in chunk {
+                        visit(n, &mut search, &mut scores);
+                    }
+                    scores
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .map(|w| w.join().expect("Worker panicked"))
+            .collect()
+    });
+
+    let mut out = NodeMap::with_capacity(len);
+    for n in g.nodes() {
+        out.insert(n, partial.iter().map(|p| p[n.0]).sum());
+    }
+    out
+}
+
+/// Single source shortest paths that keep what Brandes' algorithm needs, reused between sources
+struct Search {
+    dist: Vec<Weight>,
+    /// Number of shortest paths from the source
+    sigma: Vec<f64>,
+    preds: Vec<Vec<Node>>,
+    /// Settled by Dijkstra's algorithm
+    done: Vec<bool>,
+    /// Dependency of the source on each node, accumulated by `betweenness`
+    delta: Vec<f64>,
+    /// Reached nodes by non-decreasing distance
+    order: Vec<Node>,
+}
+
+impl Search {
+    fn new(len: usize) -> Self {
+        Self {
+            dist: vec![Weight::INFINITY; len],
+            sigma: vec![0.0; len],
+            preds: vec![Vec::new(); len],
+            done: vec![false; len],
+            delta: vec![0.0; len],
+            order: Vec::with_capacity(len),
+        }
+    }
+
+    fn reset(&mut self, source: Node) {
+        for n in self.order.drain(..) {
+            self.dist[n.0] = Weight::INFINITY;
+            self.sigma[n.0] = 0.0;
+            self.preds[n.0].clear();
+            self.done[n.0] = false;
+            self.delta[n.0] = 0.0;
+        }
+        // The source may not have been reached by the previous search
+        self.dist[source.0] = 0.0;
+        self.sigma[source.0] = 1.0;
+    }
+
+    /// Breadth first search, every edge counts as 1
+    fn bfs(&mut self, g: &AdjacencyList, source: Node) {
+        self.reset(source);
+        let mut queue = VecDeque::from([source]);
+        while let Some(n) = queue.pop_front() {
+            self.order.push(n);
+            let d = self.dist[n.0] + 1.0;
+            for e in g.edges(n) {
+                let m = e.node;
+                if self.dist[m.0] == Weight::INFINITY {
+                    self.dist[m.0] = d;
+                    queue.push_back(m);
+                }
+                if self.dist[m.0] == d {
+                    self.sigma[m.0] += self.sigma[n.0];
+                    self.preds[m.0].push(n);
+                }
+            }
+        }
+    }
+
+    /// Dijkstra's algorithm over the edge weights
+    fn dijkstra(&mut self, g: &AdjacencyList, source: Node) {
+        self.reset(source);
+        let mut queue = BinaryHeap::from([edge(source, 0.0)]);
+        while let Some(cur) = queue.pop() {
+            let n = cur.node;
+            if self.done[n.0] {
+                continue;
+            }
+            self.done[n.0] = true;
+            self.order.push(n);
+            for e in g.edges(n) {
+                let m = e.node;
+                let d = cur.weight + e.weight;
+                if d < self.dist[m.0] {
+                    self.dist[m.0] = d;
+                    self.sigma[m.0] = self.sigma[n.0];
+                    self.preds[m.0].clear();
+                    self.preds[m.0].push(n);
+                    queue.push(edge(m, d));
+                } else if d == self.dist[m.0] && !self.done[m.0] {
+                    self.sigma[m.0] += self.sigma[n.0];
+                    self.preds[m.0].push(n);
+                }
+            }
+        }
+    }
+
+    fn run(&mut self, g: &AdjacencyList, source: Node, weighted: bool) {
+        if weighted {
+            self.dijkstra(g, source);
+        } else {
+            self.bfs(g, source);
+        }
+    }
+}
+
+/// Betweenness centrality with Brandes' algorithm: for every node, the number of shortest paths
+/// between other pairs of nodes that pass through it, split evenly between equally short paths.
+///
+/// Paths follow the edge directions and every ordered pair is counted, so for undirected graphs
+/// stored with edges both ways the scores are twice the usual ones. If `weighted` is false, every
+/// edge counts as 1. The sources are spread over `threads` threads, 0 runs on one thread.
+pub fn betweenness(g: &AdjacencyList, weighted: bool, threads: usize) -> NodeMap<f64> {
+    per_source(g, threads, |source, search, scores| {
+        search.run(g, source, weighted);
+        let delta = &mut search.delta;
+        for &w in search.order.iter().rev() {
+            for &v in &search.preds[w.0] {
+                delta[v.0] += search.sigma[v.0] / search.sigma[w.0] * (1.0 + delta[w.0]);
+            }
+            if w != source {
+                scores[w.0] += delta[w.0];
+            }
+        }
+    })
+}
+
+/// Closeness centrality from every node to the nodes it can reach over weighted shortest paths:
+/// the number of other reachable nodes divided by the sum of their distances, scaled by the
+/// fraction of the graph that is reachable. Nodes that reach nothing get 0. The sources are
+/// spread over `threads` threads, 0 runs on one thread.
+pub fn closeness(g: &AdjacencyList, threads: usize) -> NodeMap<f64> {
+    let others = g.len().saturating_sub(1) as f64;
+    per_source(g, threads, |source, search, scores| {
+        search.dijkstra(g, source);
+        let total: f64 = search.order.iter().map(|n| search.dist[n.0] as f64).sum();
+        let reached = (search.order.len() - 1) as f64;
+        if total > 0.0 {
+            scores[source.0] = reached / total * (reached / others);
+        }
+    })
+}
+
+/// Harmonic centrality, the sum of the inverted weighted shortest path distances from every node
+/// to the others. Unreachable nodes add nothing, so disconnected graphs need no special care. The
+/// sources are spread over `threads` threads, 0 runs on one thread.
+pub fn harmonic(g: &AdjacencyList, threads: usize) -> NodeMap<f64> {
+    per_source(g, threads, |source, search, scores| {
+        search.dijkstra(g, source);
+        scores[source.0] = search
+            .order
+            .iter()
+            .filter(|&&n| n != source)
+            .map(|n| 1.0 / search.dist[n.0] as f64)
+            .sum();
+    })
+}
+
+/// Settings for `pagerank`
+#[derive(Debug, Clone)]
+pub struct PageRankOptions {
+    /// The probability of following an edge rather than teleporting
+    pub damping: f64,
+    /// Where teleports land, normalized before use. None for all nodes with equal probability.
+    /// Nodes without outgoing edges also teleport here.
+    pub teleport: Option<NodeMap<f64>>,
+    /// Stop when the total change of the ranks in one iteration is below this
+    pub tolerance: f64,
+    pub max_iterations: usize,
+}
+
+impl Default for PageRankOptions {
+    fn default() -> Self {
+        Self {
+            damping: 0.85,
+            teleport: None,
+            tolerance: 1e-10,
+            max_iterations: 100,
+        }
+    }
+}
+
+/// PageRank by power iteration. Each node passes its rank on in proportion to the weights of its
+/// outgoing edges. The ranks sum to 1.
+pub fn pagerank(g: &AdjacencyList, options: &PageRankOptions) -> NodeMap<f64> {
+    let len = g.len();
+    let mut out = NodeMap::with_capacity(len);
+    if len == 0 {
+        return out;
+    }
+
+    let mut teleport: Vec<f64> = match &options.teleport {
+        Some(t) => g
+            .nodes()
+            .map(|n| t.get(&n).copied().unwrap_or(0.0))
+            .collect(),
+        None => vec![1.0; len],
+    };
+    let total: f64 = teleport.iter().sum();
+    assert!(total > 0.0, "Teleport probabilities must not all be zero");
+    for t in &mut teleport {
+        *t /= total;
+    }
+    let out_weight: Vec<f64> = g
+        .nodes()
+        .map(|n| g.edges(n).map(|e| e.weight as f64).sum())
+        .collect();
+
+    let mut rank = teleport.clone();
+    let mut next = vec![0.0; len];
+    for _ in 0..options.max_iterations {
+        let dangling: f64 = g
+            .nodes()
+            .filter(|n| out_weight[n.0] == 0.0)
+            .map(|n| rank[n.0])
+            .sum();
+        for (x, t) in next.iter_mut().zip(&teleport) {
+            *x = (1.0 - options.damping + options.damping * dangling) * t;
+        }
+        for n in g.nodes() {
+            if out_weight[n.0] == 0.0 {
+                continue;
+            }
+            let share = options.damping * rank[n.0] / out_weight[n.0];
+            for e in g.edges(n) {
+                next[e.node.0] += share * e.weight as f64;
+            }
+        }
+        let change: f64 = rank.iter().zip(&next).map(|(a, b)| (a - b).abs()).sum();
+        std::mem::swap(&mut rank, &mut next);
+        if change < options.tolerance {
+            break;
+        }
+    }
+
+    for n in g.nodes() {
+        out.insert(n, rank[n.0]);
+    }
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{AdjacencyList, Node, NodeMap, PageRankOptions};
+
+    fn undirected(len: usize, edges: &[(usize, usize, f32)]) -> AdjacencyList {
+        let mut g = AdjacencyList::new();
+        let n: Vec<Node> = (0..len).map(|_| g.add_node()).collect();
+        for &(a, b, w) in edges {
+            g.add_edge(n[a], n[b], w);
+            g.add_edge(n[b], n[a], w);
+        }
+        g
+    }
+
+    fn assert_close(scores: &NodeMap<f64>, expected: &[f64]) {
+        for (i, &x) in expected.iter().enumerate() {
+            let s = scores[Node(i)];
+            assert!((s - x).abs() < 1e-6, "node {i}: {s} != {x}");
+        }
+    }
+
+    #[test]
+    fn betweenness_path_and_square() {
+        // 0 - 1 - 2 - 3, the middle nodes are on 2 and every ordered pair is counted
+        let path = undirected(4, &[(0, 1, 1.0), (1, 2, 1.0), (2, 3, 1.0)]);
+        for threads in [1, 3] {
+            assert_close(
+                &super::betweenness(&path, false, threads),
+                &[0.0, 4.0, 4.0, 0.0],
+            );
+        }
+
+        // A square 0 - 1 - 2 - 3 - 0 where 1 is expensive when weighted. Unweighted, the two
+        // paths between opposite corners are split evenly.
+        let square = undirected(4, &[(0, 1, 1.0), (1, 2, 5.0), (2, 3, 1.0), (3, 0, 1.0)]);
+        assert_close(&super::betweenness(&square, false, 2), &[1.0; 4]);
+        assert_close(&super::betweenness(&square, true, 2), &[4.0, 0.0, 0.0, 4.0]);
+    }
+
+    #[test]
+    fn closeness_and_harmonic() {
+        // A star with the hub 0, and 4 that is not connected
+        let g = undirected(5, &[(0, 1, 1.0), (0, 2, 1.0), (0, 3, 2.0)]);
+        let closeness = super::closeness(&g, 2);
+        let harmonic = super::harmonic(&g, 2);
+        assert_close(
+            &closeness,
+            &[
+                3.0 / 4.0 * (3.0 / 4.0),
+                3.0 / 6.0 * (3.0 / 4.0),
+                3.0 / 6.0 * (3.0 / 4.0),
+                3.0 / 8.0 * (3.0 / 4.0),
+                0.0,
+            ],
+        );
+        assert_close(
+            &harmonic,
+            &[
+                2.5,
+                1.0 + 0.5 + 1.0 / 3.0,
+                1.0 + 0.5 + 1.0 / 3.0,
+                0.5 + 2.0 / 3.0,
+                0.0,
+            ],
+        );
+    }
+
+    #[test]
+    fn thread_counts_agree() {
+        // 5 sources split unevenly over 3 threads as 2, 2 and 1
+        let mut g = AdjacencyList::new();
+        let n: Vec<Node> = (0..5).map(|_| g.add_node()).collect();
+        for (a, b, w) in [
+            (0, 1, 1.0),
+            (1, 2, 2.0),
+            (2, 0, 1.5),
+            (1, 3, 1.0),
+            (3, 4, 0.5),
+            (4, 2, 1.0),
+            (0, 3, 3.0),
+        ] {
+            g.add_edge(n[a], n[b], w);
+        }
+
+        let measures: [&dyn Fn(usize) -> NodeMap<f64>; 4] = [
+            &|t| super::betweenness(&g, false, t),
+            &|t| super::betweenness(&g, true, t),
+            &|t| super::closeness(&g, t),
+            &|t| super::harmonic(&g, t),
+        ];
+        for measure in measures {
+            let single = measure(1);
+            let expected: Vec<f64> = g.nodes().map(|n| single[n]).collect();
+            for threads in [0, 2, 3, 8] {
+                assert_close(&measure(threads), &expected);
+            }
+        }
+    }
+
+    #[test]
+    fn pagerank() {
+        // A directed cycle has uniform rank
+        let mut cycle = AdjacencyList::new();
+        let n: Vec<Node> = (0..4).map(|_| cycle.add_node()).collect();
+        for i in 0..4 {
+            cycle.add_edge(n[i], n[(i + 1) % 4], 1.0);
+        }
+        assert_close(
+            &super::pagerank(&cycle, &PageRankOptions::default()),
+            &[0.25; 4],
+        );
+
+        // 1 and 2 both link to 0, which has no outgoing edges
+        let mut g = AdjacencyList::new();
+        let n: Vec<Node> = (0..3).map(|_| g.add_node()).collect();
+        g.add_edge(n[1], n[0], 1.0);
+        g.add_edge(n[2], n[0], 1.0);
+        let rank = super::pagerank(&g, &PageRankOptions::default());
+        let total: f64 = g.nodes().map(|n| rank[n]).sum();
+        assert!((total - 1.0).abs() < 1e-9);
+        assert!(rank[n[0]] > rank[n[1]]);
+        assert!((rank[n[1]] - rank[n[2]]).abs() < 1e-9);
+
+        // Teleporting only to 1 makes 2 unreachable
+        let mut teleport = NodeMap::new();
+        teleport.insert(n[1], 1.0);
+        let options = PageRankOptions {
+            teleport: Some(teleport),
+            ..PageRankOptions::default()
+        };
+        let rank = super::pagerank(&g, &options);
+        assert_eq!(rank[n[2]], 0.0);
+        assert!(rank[n[1]] > rank[n[0]]);
+    }
+}
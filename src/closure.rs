@@ -0,0 +1,157 @@
+use crate::{topsort_dag, AdjacencyList, CycleError, Node};
+
+/// One bit per node, the nodes reachable from each node in a DAG
+struct ReachRows {
+    words: usize,
+    bits: Vec<u64>,
+}
+
+impl ReachRows {
+    fn new(g: &AdjacencyList) -> Result<Self, CycleError> {
+        let order = topsort_dag(g)?;
+        let words = g.len().div_ceil(64);
+        let mut rows = Self {
+            words,
+            bits: vec![0; words * g.len()],
+        };
+        // Children come later in the order, so they are done before their parents
+        for &n in order.iter().rev() {
+            for e in g.edges(n) {
+                rows.set(n, e.node);
+                rows.union(n, e.node);
+            }
+        }
+        Ok(rows)
+    }
+
+    fn row(&self, n: Node) -> &[u64] {
+        &self.bits[n.0 * self.words..(n.0 + 1) * self.words]
+    }
+
+    fn set(&mut self, n: Node, m: Node) {
+        self.bits[n.0 * self.words + m.0 / 64] |= 1 << (m.0 % 64);
+    }
+
+    /// Add everything reachable from `from` to the row of `to`
+    fn union(&mut self, to: Node, from: Node) {
+        for i in 0..self.words {
+            self.bits[to.0 * self.words + i] |= self.bits[from.0 * self.words + i];
+        }
+    }
+
+    fn has(&self, n: Node, m: Node) -> bool {
+        self.row(n)[m.0 / 64] & (1 << (m.0 % 64)) != 0
+    }
+}
+
+fn empty_like(g: &AdjacencyList) -> AdjacencyList {
+    let mut out = AdjacencyList::with_capacity(g.len());
+    for _ in g.nodes() {
+        out.add_node();
+    }
+    out
+}
+
+/// A graph with an edge from a to b for every b reachable from a in the DAG g. Edges that are in
+/// g keep their weight, the rest get weight 1.
+pub fn transitive_closure(g: &AdjacencyList) -> Result<AdjacencyList, CycleError> {
+    let rows = ReachRows::new(g)?;
+    let mut out = empty_like(g);
+    for n in g.nodes() {
+        for e in g.edges(n) {
+            out.add_edge(n, e.node, e.weight);
+        }
+        for m in g.nodes().filter(|&m| rows.has(n, m)) {
+            out.add_edge(n, m, 1.0);
+        }
+    }
+    Ok(out)
+}
+
+/// The smallest graph with the same reachability as the DAG g, g without every edge a -> b where
+/// b can also be reached through another child of a. The kept edges keep their weight.
+pub fn transitive_reduction(g: &AdjacencyList) -> Result<AdjacencyList, CycleError> {
+    let rows = ReachRows::new(g)?;
+    let mut out = empty_like(g);
+    let mut indirect = vec![0u64; rows.words];
+    for n in g.nodes() {
+        indirect.iter_mut().for_each(|w| *w = 0);
+        for e in g.edges(n) {
+            for (w, r) in indirect.iter_mut().zip(rows.row(e.node)) {
+                *w |= r;
+            }
+        }
+        for e in g.edges(n) {
+            if indirect[e.node.0 / 64] & (1 << (e.node.0 % 64)) == 0 {
+                out.add_edge(n, e.node, e.weight);
+            }
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{AdjacencyList, CycleError, Node};
+
+    fn edges(g: &AdjacencyList) -> Vec<(usize, usize)> {
+        let mut out: Vec<(usize, usize)> = g
+            .nodes()
+            .flat_map(|n| g.edges(n).map(move |e| (n.0, e.node.0)))
+            .collect();
+        out.sort();
+        out
+    }
+
+    // 0 -> 1 -> 2 -> 3 with the shortcuts 0 -> 2 and 0 -> 3, and 4 -> 3
+    fn example() -> AdjacencyList {
+        let mut g = AdjacencyList::new();
+        let n: Vec<Node> = (0..5).map(|_| g.add_node()).collect();
+        for (a, b) in [(0, 1), (1, 2), (2, 3), (0, 2), (0, 3), (4, 3)] {
+            g.add_edge(n[a], n[b], 2.0);
+        }
+        g
+    }
+
+    #[test]
+    fn closure() {
+        let closure = crate::transitive_closure(&example()).unwrap();
+        assert_eq!(
+            edges(&closure),
+            [(0, 1), (0, 2), (0, 3), (1, 2), (1, 3), (2, 3), (4, 3)]
+        );
+        assert_eq!(closure.edges(Node(1)).map(|e| e.weight).sum::<f32>(), 3.0);
+    }
+
+    #[test]
+    fn reduction() {
+        let reduction = crate::transitive_reduction(&example()).unwrap();
+        assert_eq!(edges(&reduction), [(0, 1), (1, 2), (2, 3), (4, 3)]);
+        assert!(reduction.edges(Node(0)).all(|e| e.weight == 2.0));
+    }
+
+    #[test]
+    fn wide() {
+        // More nodes than fit in a single word, a chain with every shortcut
+        let mut g = AdjacencyList::new();
+        let n: Vec<Node> = (0..70).map(|_| g.add_node()).collect();
+        for i in 0..70 {
+            for j in i + 1..70 {
+                g.add_edge(n[i], n[j], 1.0);
+            }
+        }
+        let reduction = crate::transitive_reduction(&g).unwrap();
+        let expected: Vec<(usize, usize)> = (0..69).map(|i| (i, i + 1)).collect();
+        assert_eq!(edges(&reduction), expected);
+        let closure = crate::transitive_closure(&reduction).unwrap();
+        assert_eq!(edges(&closure), edges(&g));
+    }
+
+    #[test]
+    fn cycle() {
+        let mut g = example();
+        g.add_edge(Node(3), Node(1), 1.0);
+        assert_eq!(crate::transitive_closure(&g).err(), Some(CycleError));
+        assert_eq!(crate::transitive_reduction(&g).err(), Some(CycleError));
+    }
+}
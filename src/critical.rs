@@ -0,0 +1,146 @@
+use crate::{edge, topsort_dag, AdjacencyList, CycleError, Node, NodeMap, Path, Weight};
+
+/// The result of `critical_path`
+pub struct Schedule {
+    /// The earliest time each node can start
+    pub earliest_start: NodeMap<Weight>,
+    /// The latest time each node can start without delaying the end of the schedule
+    pub latest_start: NodeMap<Weight>,
+    /// How much each node can be delayed, zero for the nodes on a critical path
+    pub slack: NodeMap<Weight>,
+    /// The time when every node is done
+    pub makespan: Weight,
+    /// A longest chain of nodes, where each entry has the time from the start of the previous
+    /// node to its own start. The path cost is the start time of the last node.
+    pub critical_path: Path,
+}
+
+/// Critical path analysis of a DAG where node n takes `duration(n)` and an edge a -> b with
+/// weight w means that b can start w after a is done.
+pub fn critical_path(
+    g: &AdjacencyList,
+    duration: impl Fn(Node) -> Weight,
+) -> Result<Schedule, CycleError> {
+    let order = topsort_dag(g)?;
+    let len = g.len();
+    let durations: Vec<Weight> = g.nodes().map(&duration).collect();
+
+    // Forward pass, remembering the predecessor that decides each start time
+    let mut earliest: Vec<Weight> = vec![0.0; len];
+    let mut decided_by: Vec<Option<(Node, Weight)>> = vec![None; len];
+    for &n in &order {
+        let done = earliest[n.0] + durations[n.0];
+        for e in g.edges(n) {
+            let start = done + e.weight;
+            if decided_by[e.node.0].is_none() || start > earliest[e.node.0] {
+                earliest[e.node.0] = start;
+                decided_by[e.node.0] = Some((n, start - earliest[n.0]));
+            }
+        }
+    }
+
+    let last = g
+        .nodes()
+        .max_by(|a, b| {
+            (earliest[a.0] + durations[a.0])
+                .partial_cmp(&(earliest[b.0] + durations[b.0]))
+                .expect("Invalid float")
+        })
+        .map(|n| n.0);
+    let makespan = last.map_or(0.0, |i| earliest[i] + durations[i]);
+
+    // Backward pass
+    let mut latest: Vec<Weight> = vec![Weight::INFINITY; len];
+    for &n in order.iter().rev() {
+        let must_finish = g
+            .edges(n)
+            .map(|e| latest[e.node.0] - e.weight)
+            .fold(makespan, Weight::min);
+        latest[n.0] = must_finish - durations[n.0];
+    }
+
+    let mut critical_path = Path::new();
+    if let Some(i) = last {
+        let mut n = Node(i);
+        while let Some((parent, gap)) = decided_by[n.0] {
+            critical_path.push(edge(n, gap));
+            n = parent;
+        }
+        critical_path.push(edge(n, 0.0));
+        critical_path.reverse();
+    }
+
+    let mut earliest_start = NodeMap::with_capacity(len);
+    let mut latest_start = NodeMap::with_capacity(len);
+    let mut slack = NodeMap::with_capacity(len);
+    for n in g.nodes() {
+        earliest_start.insert(n, earliest[n.0]);
+        latest_start.insert(n, latest[n.0]);
+        slack.insert(n, latest[n.0] - earliest[n.0]);
+    }
+
+    Ok(Schedule {
+        earliest_start,
+        latest_start,
+        slack,
+        makespan,
+        critical_path,
+    })
+}
+
+/// The longest path in a DAG by edge weights
+pub fn longest_path(g: &AdjacencyList) -> Result<Path, CycleError> {
+    critical_path(g, |_| 0.0).map(|s| s.critical_path)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{path_cost, AdjacencyList, CycleError, Node};
+
+    // Tasks with durations, 0 -> {1, 2} -> 3 and an independent task 4
+    fn example() -> (AdjacencyList, [f32; 5]) {
+        let mut g = AdjacencyList::new();
+        let n: Vec<Node> = (0..5).map(|_| g.add_node()).collect();
+        g.add_edge(n[0], n[1], 0.0);
+        g.add_edge(n[0], n[2], 0.0);
+        g.add_edge(n[1], n[3], 0.0);
+        g.add_edge(n[2], n[3], 1.0);
+        (g, [2.0, 3.0, 1.0, 4.0, 5.0])
+    }
+
+    #[test]
+    fn schedule() {
+        let (g, durations) = example();
+        let s = super::critical_path(&g, |n| durations[n.0]).unwrap();
+        assert_eq!(s.makespan, 9.0);
+        let es: Vec<f32> = g.nodes().map(|n| s.earliest_start[n]).collect();
+        let ls: Vec<f32> = g.nodes().map(|n| s.latest_start[n]).collect();
+        let slack: Vec<f32> = g.nodes().map(|n| s.slack[n]).collect();
+        assert_eq!(es, [0.0, 2.0, 2.0, 5.0, 0.0]);
+        assert_eq!(ls, [0.0, 2.0, 3.0, 5.0, 4.0]);
+        assert_eq!(slack, [0.0, 0.0, 1.0, 0.0, 4.0]);
+
+        let nodes: Vec<Node> = s.critical_path.iter().map(|e| e.node).collect();
+        assert_eq!(nodes, [Node(0), Node(1), Node(3)]);
+        assert_eq!(path_cost(&s.critical_path), s.earliest_start[Node(3)]);
+    }
+
+    #[test]
+    fn longest_path() {
+        let mut g = AdjacencyList::new();
+        let n: Vec<Node> = (0..4).map(|_| g.add_node()).collect();
+        g.add_edge(n[0], n[1], 1.0);
+        g.add_edge(n[1], n[3], 1.0);
+        g.add_edge(n[0], n[2], 2.0);
+        g.add_edge(n[2], n[3], 3.0);
+        let path = super::longest_path(&g).unwrap();
+        assert_eq!(
+            path.iter().map(|e| e.node).collect::<Vec<_>>(),
+            [n[0], n[2], n[3]]
+        );
+        assert_eq!(path_cost(&path), 5.0);
+
+        g.add_edge(n[3], n[0], 1.0);
+        assert_eq!(super::longest_path(&g).err(), Some(CycleError));
+    }
+}
@@ -0,0 +1,148 @@
+use crate::{walk_backwards, AdjacencyList, Edge, Node, NodeMap, Path, Weight};
+
+use std::collections::BinaryHeap;
+
+/// Dijkstra from `sources`, only following edges for which `allowed(from, edge)` is true. Stops
+/// early when `target` is reached.
+pub(crate) fn dijkstra_impl(
+    g: &AdjacencyList,
+    sources: &[Node],
+    target: Option<Node>,
+    allowed: impl Fn(Node, &Edge) -> bool,
+    node_cost: &mut NodeMap<Weight>,
+    parents: &mut NodeMap<Edge>,
+) {
+    let mut queue: BinaryHeap<Edge> = BinaryHeap::with_capacity(g.len());
+    for &s in sources {
+        node_cost.insert(s, 0.0);
+        queue.push(Edge {
+            node: s,
+            weight: 0.0,
+        });
+    }
+
+    while let Some(Edge {
+        node: cur,
+        weight: cur_cost,
+    }) = queue.pop()
+    {
+        if cur_cost > node_cost[cur] {
+            // Stale entry, cur was pushed again with a lower cost
+            continue;
+        }
+
+        if Some(cur) == target {
+            return;
+        }
+
+        for &Edge {
+            node: child,
+            weight: cost,
+        } in g.edges(cur).filter(|e| allowed(cur, e))
+        {
+            let start_to_child_cost = cur_cost + cost;
+            if !node_cost.has(&child) || start_to_child_cost < node_cost[child] {
+                node_cost.insert(child, start_to_child_cost);
+                parents.insert(
+                    child,
+                    Edge {
+                        node: cur,
+                        weight: cost,
+                    },
+                );
+                queue.push(Edge {
+                    node: child,
+                    weight: start_to_child_cost,
+                });
+            }
+        }
+    }
+}
+
+/// Compute the distance from the closest of `sources` to every reachable node.
+///
+/// Unreachable nodes are not in the returned map.
+pub fn multi_source_dijkstra(g: &AdjacencyList, sources: &[Node]) -> NodeMap<Weight> {
+    let mut node_cost = NodeMap::with_capacity(g.len());
+    let mut parents = NodeMap::with_capacity(g.len());
+    dijkstra_impl(g, sources, None, |_, _| true, &mut node_cost, &mut parents);
+    node_cost
+}
+
+/// Find the shortest path between two nodes, without a heuristic
+pub fn shortest_path(g: &AdjacencyList, start: Node, end: Node) -> Option<Path> {
+    shortest_path_filtered(g, start, end, |_, _| true)
+}
+
+pub(crate) fn shortest_path_filtered(
+    g: &AdjacencyList,
+    start: Node,
+    end: Node,
+    allowed: impl Fn(Node, &Edge) -> bool,
+) -> Option<Path> {
+    let mut node_cost = NodeMap::with_capacity(g.len());
+    let mut parents = NodeMap::with_capacity(g.len());
+    dijkstra_impl(
+        g,
+        &[start],
+        Some(end),
+        allowed,
+        &mut node_cost,
+        &mut parents,
+    );
+    walk_backwards(&start, &end, &parents)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{AdjacencyList, Node};
+
+    fn line(len: usize) -> AdjacencyList {
+        let mut g = AdjacencyList::new();
+        let nodes: Vec<Node> = (0..len).map(|_| g.add_node()).collect();
+        for w in nodes.windows(2) {
+            g.add_edge(w[0], w[1], 2.0);
+            g.add_edge(w[1], w[0], 2.0);
+        }
+        g
+    }
+
+    #[test]
+    fn single_source() {
+        let g = line(4);
+        let dist = crate::multi_source_dijkstra(&g, &[Node(0)]);
+        let actual: Vec<f32> = g.nodes().map(|n| dist[n]).collect();
+        assert_eq!(actual, vec![0.0, 2.0, 4.0, 6.0]);
+    }
+
+    #[test]
+    fn closest_source_wins() {
+        let g = line(5);
+        let dist = crate::multi_source_dijkstra(&g, &[Node(0), Node(4)]);
+        let actual: Vec<f32> = g.nodes().map(|n| dist[n]).collect();
+        assert_eq!(actual, vec![0.0, 2.0, 4.0, 2.0, 0.0]);
+    }
+
+    #[test]
+    fn unreachable() {
+        let mut g = line(3);
+        let lonely = g.add_node();
+        let dist = crate::multi_source_dijkstra(&g, &[Node(0)]);
+        assert!(!dist.has(&lonely));
+        assert_eq!(dist.iter().count(), 3);
+    }
+
+    #[test]
+    fn shortest_path() {
+        let mut g = line(4);
+        g.add_edge(Node(0), Node(3), 5.0);
+        let path = crate::shortest_path(&g, Node(0), Node(3)).unwrap();
+        assert_eq!(path.len(), 2);
+        assert_eq!(path[1].node, Node(3));
+        assert_eq!(path[1].weight, 5.0);
+
+        let path = crate::shortest_path(&g, Node(3), Node(0)).unwrap();
+        assert_eq!(path.len(), 4);
+        assert_eq!(crate::path_cost(&path), 6.0);
+    }
+}
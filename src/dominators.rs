@@ -0,0 +1,192 @@
+use crate::{dfs_visit, AdjacencyList, Control, DfsVisitor, Node, NodeMap};
+
+const UNDEFINED: usize = usize::MAX;
+
+struct Postorder(Vec<Node>);
+
+impl DfsVisitor for Postorder {
+    fn finish(&mut self, n: Node, _time: usize) -> Control {
+        self.0.push(n);
+        Control::Continue
+    }
+}
+
+/// Immediate dominators of all nodes reachable from `root`, with the iterative algorithm by
+/// Cooper, Harvey and Kennedy.
+///
+/// The root maps to itself. Nodes that can't be reached from the root are left out.
+pub fn dominators(g: &AdjacencyList, root: Node) -> NodeMap<Node> {
+    let mut postorder = Postorder(Vec::with_capacity(g.len()));
+    dfs_visit(g, [root], &mut postorder);
+    let postorder = postorder.0;
+
+    let mut number = vec![UNDEFINED; g.len()];
+    for (i, n) in postorder.iter().enumerate() {
+        number[n.0] = i;
+    }
+    let preds = g.inverted();
+
+    // Indexed by postorder number
+    let mut idom = vec![UNDEFINED; postorder.len()];
+    let root_number = number[root.0];
+    idom[root_number] = root_number;
+
+    let intersect = |idom: &[usize], mut a: usize, mut b: usize| {
+        while a != b {
+            while a < b {
+                a = idom[a];
+            }
+            while b < a {
+                b = idom[b];
+            }
+        }
+        a
+    };
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &n in postorder.iter().rev().skip(1) {
+            let mut new_idom = UNDEFINED;
+            for p in preds.edges(n) {
+                let p = number[p.node.0];
+                if p == UNDEFINED || idom[p] == UNDEFINED {
+                    continue;
+                }
+                new_idom = if new_idom == UNDEFINED {
+                    p
+                } else {
+                    intersect(&idom, p, new_idom)
+                };
+            }
+            let b = number[n.0];
+            if idom[b] != new_idom {
+                idom[b] = new_idom;
+                changed = true;
+            }
+        }
+    }
+
+    let mut out = NodeMap::with_capacity(g.len());
+    for (i, &d) in idom.iter().enumerate() {
+        out.insert(postorder[i], postorder[d]);
+    }
+    out
+}
+
+/// Immediate post-dominators of all nodes that can reach `exit`, the dominators of the
+/// inverted graph. The exit maps to itself.
+pub fn post_dominators(g: &AdjacencyList, exit: Node) -> NodeMap<Node> {
+    dominators(&g.inverted(), exit)
+}
+
+/// The dominance frontier of every node in `idom`, as computed by `dominators`: the nodes where
+/// its dominance ends. Each frontier is sorted.
+pub fn dominance_frontiers(g: &AdjacencyList, idom: &NodeMap<Node>) -> NodeMap<Vec<Node>> {
+    let preds = g.inverted();
+    let mut frontiers: NodeMap<Vec<Node>> = NodeMap::with_capacity(g.len());
+    for (n, _) in idom.iter() {
+        frontiers.insert(n, Vec::new());
+    }
+
+    for (n, &dom) in idom.iter() {
+        let reachable: Vec<Node> = preds
+            .edges(n)
+            .map(|e| e.node)
+            .filter(|p| idom.has(p))
+            .collect();
+        if reachable.len() < 2 {
+            continue;
+        }
+        for mut runner in reachable {
+            while runner != dom {
+                let frontier = frontiers.get_mut(&runner).expect("Reachable node");
+                if !frontier.contains(&n) {
+                    frontier.push(n);
+                }
+                runner = idom[runner];
+            }
+        }
+    }
+
+    for (n, _) in idom.iter() {
+        frontiers.get_mut(&n).expect("Reachable node").sort();
+    }
+    frontiers
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{AdjacencyList, Node, NodeMap};
+
+    // 0 -> 1, a diamond 1 -> {2, 3} -> 4, a loop 4 -> 1 and the exit 4 -> 5. 6 is unreachable.
+    fn example() -> AdjacencyList {
+        let mut g = AdjacencyList::new();
+        let n: Vec<Node> = (0..7).map(|_| g.add_node()).collect();
+        for (a, b) in [
+            (0, 1),
+            (1, 2),
+            (1, 3),
+            (2, 4),
+            (3, 4),
+            (4, 1),
+            (4, 5),
+            (6, 5),
+        ] {
+            g.add_edge(n[a], n[b], 1.0);
+        }
+        g
+    }
+
+    fn as_vec(map: &NodeMap<Node>, len: usize) -> Vec<Option<usize>> {
+        (0..len).map(|i| map.get(&Node(i)).map(|n| n.0)).collect()
+    }
+
+    #[test]
+    fn dominators() {
+        let g = example();
+        let idom = crate::dominators(&g, Node(0));
+        assert_eq!(
+            as_vec(&idom, 7),
+            [Some(0), Some(0), Some(1), Some(1), Some(1), Some(4), None]
+        );
+
+        let frontiers = crate::dominance_frontiers(&g, &idom);
+        assert_eq!(frontiers[Node(0)], []);
+        assert_eq!(frontiers[Node(1)], [Node(1)]);
+        assert_eq!(frontiers[Node(2)], [Node(4)]);
+        assert_eq!(frontiers[Node(3)], [Node(4)]);
+        assert_eq!(frontiers[Node(4)], [Node(1)]);
+        assert!(frontiers.get(&Node(6)).is_none());
+    }
+
+    #[test]
+    fn post_dominators() {
+        let g = example();
+        let ipdom = crate::post_dominators(&g, Node(5));
+        assert_eq!(
+            as_vec(&ipdom, 7),
+            [
+                Some(1),
+                Some(4),
+                Some(4),
+                Some(4),
+                Some(5),
+                Some(5),
+                Some(5)
+            ]
+        );
+    }
+
+    #[test]
+    fn irreducible() {
+        // 1 and 2 form a loop with two entries, neither dominates the other
+        let mut g = AdjacencyList::new();
+        let n: Vec<Node> = (0..4).map(|_| g.add_node()).collect();
+        for (a, b) in [(0, 1), (0, 2), (1, 2), (2, 1), (2, 3)] {
+            g.add_edge(n[a], n[b], 1.0);
+        }
+        let idom = crate::dominators(&g, n[0]);
+        assert_eq!(as_vec(&idom, 4), [Some(0), Some(0), Some(0), Some(2)]);
+    }
+}
@@ -0,0 +1,182 @@
+use crate::{topsort_dag, AdjacencyList, CycleError, Node, NodeSet, Weight};
+
+/// A DAG that keeps a topological order up to date as edges are added, with the algorithm by
+/// Pearce and Kelly. Adding an edge only reorders the nodes between its endpoints in the current
+/// order that are connected to them.
+#[derive(Clone, Default)]
+pub struct DynamicTopsort {
+    graph: AdjacencyList,
+    inverse: AdjacencyList,
+    /// The position of each node in `order`
+    position: Vec<usize>,
+    order: Vec<Node>,
+    visited: NodeSet,
+}
+
+impl DynamicTopsort {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start from an existing graph, fails if it has a cycle
+    pub fn from_graph(g: &AdjacencyList) -> Result<Self, CycleError> {
+        let order = topsort_dag(g)?;
+        let mut position = vec![0; g.len()];
+        for (i, n) in order.iter().enumerate() {
+            position[n.0] = i;
+        }
+        Ok(Self {
+            graph: g.clone(),
+            inverse: g.inverted(),
+            position,
+            order,
+            visited: NodeSet::with_capacity(g.len()),
+        })
+    }
+
+    /// Add a node, it goes last in the order
+    pub fn add_node(&mut self) -> Node {
+        let n = self.graph.add_node();
+        self.inverse.add_node();
+        self.position.push(self.order.len());
+        self.order.push(n);
+        n
+    }
+
+    /// Add the edge a -> b and move nodes as needed so that a comes before b. If the edge would
+    /// close a cycle, nothing is changed.
+    pub fn add_edge(&mut self, a: Node, b: Node, w: Weight) -> Result<(), CycleError> {
+        if a == b {
+            return Err(CycleError);
+        }
+        let (lower, upper) = (self.position[b.0], self.position[a.0]);
+        if lower < upper {
+            // Everything after b that comes before a needs to move past a
+            let Some(forward) = self.search(b, upper, true) else {
+                return Err(CycleError);
+            };
+            let backward = self
+                .search(a, lower, false)
+                .expect("Backward search can't close a cycle");
+            self.reorder(forward, backward);
+        }
+        self.graph.add_edge(a, b, w);
+        self.inverse.add_edge(b, a, w);
+        Ok(())
+    }
+
+    /// Depth first search from `start` in the affected region, the nodes with positions below
+    /// `bound` going forward or above it going backward. Returns None if the forward search
+    /// reaches `bound`, which means the new edge closes a cycle.
+    fn search(&mut self, start: Node, bound: usize, forward: bool) -> Option<Vec<Node>> {
+        let graph = if forward { &self.graph } else { &self.inverse };
+        let mut found = vec![start];
+        let mut stack = vec![start];
+        self.visited.add(start);
+        while let Some(n) = stack.pop() {
+            for e in graph.edges(n) {
+                let p = self.position[e.node.0];
+                if forward && p == bound {
+                    for &n in &found {
+                        self.visited.remove(n);
+                    }
+                    return None;
+                }
+                let inside = if forward { p < bound } else { p > bound };
+                if inside && self.visited.add(e.node) {
+                    found.push(e.node);
+                    stack.push(e.node);
+                }
+            }
+        }
+        Some(found)
+    }
+
+    /// Give the nodes reached backwards the first of the affected positions, followed by the
+    /// nodes reached forward. Both keep their relative order.
+    fn reorder(&mut self, mut forward: Vec<Node>, mut backward: Vec<Node>) {
+        forward.sort_by_key(|n| self.position[n.0]);
+        backward.sort_by_key(|n| self.position[n.0]);
+        let mut slots: Vec<usize> = backward
+            .iter()
+            .chain(&forward)
+            .map(|n| self.position[n.0])
+            .collect();
+        slots.sort_unstable();
+        for (&n, slot) in backward.iter().chain(&forward).zip(slots) {
+            self.visited.remove(n);
+            self.position[n.0] = slot;
+            self.order[slot] = n;
+        }
+    }
+
+    /// All nodes, every edge goes from an earlier to a later node
+    pub fn order(&self) -> &[Node] {
+        &self.order
+    }
+
+    /// The index of n in `order`
+    pub fn position(&self, n: Node) -> usize {
+        self.position[n.0]
+    }
+
+    pub fn graph(&self) -> &AdjacencyList {
+        &self.graph
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{AdjacencyList, CycleError, DynamicTopsort, Node};
+
+    fn check(dt: &DynamicTopsort) {
+        let g = dt.graph();
+        for n in g.nodes() {
+            assert_eq!(dt.order()[dt.position(n)], n);
+            for e in g.edges(n) {
+                assert!(dt.position(n) < dt.position(e.node));
+            }
+        }
+    }
+
+    #[test]
+    fn reorders_affected_region() {
+        let mut dt = DynamicTopsort::new();
+        let n: Vec<Node> = (0..6).map(|_| dt.add_node()).collect();
+        dt.add_edge(n[4], n[5], 1.0).unwrap();
+        dt.add_edge(n[1], n[2], 1.0).unwrap();
+        assert_eq!(dt.order(), n.as_slice());
+
+        // 5 has to move before 1, dragging 4 with it. 3 is not connected and stays put.
+        dt.add_edge(n[5], n[1], 1.0).unwrap();
+        check(&dt);
+        assert_eq!(dt.order(), [n[0], n[4], n[5], n[3], n[1], n[2]]);
+
+        dt.add_edge(n[3], n[0], 1.0).unwrap();
+        check(&dt);
+        assert_eq!(dt.add_edge(n[2], n[4], 1.0), Err(CycleError));
+        assert_eq!(dt.add_edge(n[0], n[0], 1.0), Err(CycleError));
+        assert!(!dt.graph().has_edge(n[2], n[4]));
+        check(&dt);
+    }
+
+    #[test]
+    fn random_edges() {
+        let mut rand = crate::test_rng(0x853c49e6748fea9b);
+
+        let mut g = AdjacencyList::new();
+        for _ in 0..30 {
+            g.add_node();
+        }
+        g.add_edge(Node(3), Node(7), 1.0);
+        let mut dt = DynamicTopsort::from_graph(&g).unwrap();
+        for _ in 0..200 {
+            let (a, b) = (Node(rand(30) as usize), Node(rand(30) as usize));
+            let mut with_edge = dt.graph().clone();
+            with_edge.add_edge(a, b, 1.0);
+            let acyclic = crate::topsort(&with_edge).len() == with_edge.len();
+            assert_eq!(dt.add_edge(a, b, 1.0).is_ok(), acyclic);
+            check(&dt);
+        }
+    }
+}
@@ -0,0 +1,403 @@
+use crate::dijkstra::dijkstra_impl;
+use crate::{
+    edge, max_weight_matching, min_cost_flow, undirected_edges, undirected_graph, walk_backwards,
+    AdjacencyList, Edge, MinCostFlowError, Node, NodeMap, Path, Weight,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EulerError {
+    /// The edges are not all connected, or for the postman tour, some edge can't be returned from
+    Disconnected,
+    /// The degrees don't allow a trail: more than two odd nodes in an undirected graph, or nodes
+    /// with in-degree different from out-degree beyond a single start and end in a directed one
+    Unbalanced,
+}
+
+impl std::fmt::Display for EulerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Disconnected => f.write_str("Edges are not connected"),
+            Self::Unbalanced => f.write_str("Node degrees don't allow an Eulerian trail"),
+        }
+    }
+}
+
+impl std::error::Error for EulerError {}
+
+/// Hierholzer's algorithm on a multigraph given as an edge list, which may contain the same edge
+/// several times
+fn hierholzer(
+    len: usize,
+    edges: &[(Node, Node, Weight)],
+    directed: bool,
+    circuit: bool,
+) -> Result<Path, EulerError> {
+    if edges.is_empty() {
+        return Ok(Path::new());
+    }
+
+    let mut adj: Vec<Vec<(usize, Node)>> = vec![Vec::new(); len];
+    let mut balance: Vec<i64> = vec![0; len];
+    for (i, &(a, b, _)) in edges.iter().enumerate() {
+        adj[a.0].push((i, b));
+        balance[a.0] += 1;
+        if directed {
+            balance[b.0] -= 1;
+        } else {
+            adj[b.0].push((i, a));
+            balance[b.0] += 1;
+        }
+    }
+
+    // Directed: the start has one more outgoing than incoming edge. Undirected: an odd node.
+    let unbalanced: Vec<usize> = if directed {
+        if balance.iter().any(|b| b.abs() > 1) {
+            return Err(EulerError::Unbalanced);
+        }
+        (0..len).filter(|&i| balance[i] != 0).collect()
+    } else {
+        (0..len).filter(|&i| balance[i] % 2 != 0).collect()
+    };
+    if unbalanced.len() > 2 || (circuit && !unbalanced.is_empty()) {
+        return Err(EulerError::Unbalanced);
+    }
+    let start = if directed {
+        unbalanced.iter().copied().find(|&i| balance[i] > 0)
+    } else {
+        unbalanced.first().copied()
+    };
+    let start = start.unwrap_or_else(|| edges[0].0 .0);
+
+    let mut used = vec![false; edges.len()];
+    let mut next = vec![0; len];
+    let mut stack: Vec<Edge> = vec![edge(Node(start), 0.0)];
+    let mut path = Path::with_capacity(edges.len() + 1);
+    while let Some(&Edge { node: n, .. }) = stack.last() {
+        while next[n.0] < adj[n.0].len() && used[adj[n.0][next[n.0]].0] {
+            next[n.0] += 1;
+        }
+        match adj[n.0].get(next[n.0]) {
+            Some(&(i, other)) => {
+                used[i] = true;
+                stack.push(edge(other, edges[i].2));
+            }
+            None => path.push(stack.pop().expect("Stack is not empty")),
+        }
+    }
+
+    if path.len() != edges.len() + 1 {
+        return Err(EulerError::Disconnected);
+    }
+    // Nodes are popped from the end of the trail, each with the weight of the edge into it
+    path.reverse();
+    Ok(path)
+}
+
+fn directed_edges(g: &AdjacencyList) -> Vec<(Node, Node, Weight)> {
+    g.nodes()
+        .flat_map(|n| g.edges(n).map(move |e| (n, e.node, e.weight)))
+        .collect()
+}
+
+/// A trail that uses every edge of the directed graph g exactly once. If there is an Eulerian
+/// circuit, the trail is closed. A graph without edges gives an empty path.
+pub fn euler_path(g: &AdjacencyList) -> Result<Path, EulerError> {
+    hierholzer(g.len(), &directed_edges(g), true, false)
+}
+
+/// A closed trail that uses every edge of the directed graph g exactly once
+pub fn euler_circuit(g: &AdjacencyList) -> Result<Path, EulerError> {
+    hierholzer(g.len(), &directed_edges(g), true, true)
+}
+
+/// `euler_path` where g is treated as undirected, an edge in both directions is used once.
+/// Self loops are ignored.
+pub fn undirected_euler_path(g: &AdjacencyList) -> Result<Path, EulerError> {
+    hierholzer(g.len(), &undirected_edges(g), false, false)
+}
+
+/// `euler_circuit` where g is treated as undirected, an edge in both directions is used once.
+/// Self loops are ignored.
+pub fn undirected_euler_circuit(g: &AdjacencyList) -> Result<Path, EulerError> {
+    hierholzer(g.len(), &undirected_edges(g), false, true)
+}
+
+/// The shortest closed walk that uses every edge of the directed graph g at least once. The
+/// edges to walk twice are found with a minimum cost flow from the nodes with more incoming than
+/// outgoing edges to those with more outgoing. Weights must not be negative.
+pub fn chinese_postman(g: &AdjacencyList) -> Result<Path, EulerError> {
+    let mut edges = directed_edges(g);
+    assert!(edges.iter().all(|e| e.2 >= 0.0), "Negative edge weight");
+
+    let mut balance: Vec<Weight> = vec![0.0; g.len()];
+    for &(a, b, _) in &edges {
+        balance[a.0] -= 1.0;
+        balance[b.0] += 1.0;
+    }
+    let mut supply = NodeMap::with_capacity(g.len());
+    for n in g.nodes().filter(|n| balance[n.0] != 0.0) {
+        supply.insert(n, balance[n.0]);
+    }
+
+    if supply.iter().next().is_some() {
+        // No edge needs to be repeated more often than the total imbalance
+        let unbounded: Weight = balance.iter().map(|b| b.abs()).sum();
+        let extra = min_cost_flow(g, |_, _| unbounded, &supply).map_err(|e| match e {
+            MinCostFlowError::Infeasible => EulerError::Disconnected,
+            MinCostFlowError::Unbalanced => unreachable!("Degrees always sum to zero"),
+        })?;
+        for n in extra.flow.nodes() {
+            for e in extra.flow.edges(n) {
+                let w = g
+                    .edges(n)
+                    .find(|x| x.node == e.node)
+                    .expect("Flow along an edge of g")
+                    .weight;
+                for _ in 0..e.weight.round() as usize {
+                    edges.push((n, e.node, w));
+                }
+            }
+        }
+    }
+
+    hierholzer(g.len(), &edges, true, true)
+}
+
+/// `chinese_postman` where g is treated as undirected. The odd nodes are paired up with a minimum
+/// weight perfect matching on their shortest path distances and the paths between the pairs are
+/// walked twice. Weights must not be negative.
+pub fn undirected_chinese_postman(g: &AdjacencyList) -> Result<Path, EulerError> {
+    let mut edges = undirected_edges(g);
+    assert!(edges.iter().all(|e| e.2 >= 0.0), "Negative edge weight");
+    let undirected = undirected_graph(g.len(), &edges);
+
+    let mut degree = vec![0; g.len()];
+    for &(a, b, _) in &edges {
+        degree[a.0] += 1;
+        degree[b.0] += 1;
+    }
+    let odd: Vec<Node> = g.nodes().filter(|n| degree[n.0] % 2 == 1).collect();
+
+    if !odd.is_empty() {
+        // Shortest paths between all odd nodes
+        let mut paths: Vec<Vec<Option<Path>>> = Vec::with_capacity(odd.len());
+        for &a in &odd {
+            let mut cost = NodeMap::with_capacity(g.len());
+            let mut parents = NodeMap::with_capacity(g.len());
+            dijkstra_impl(
+                &undirected,
+                &[a],
+                None,
+                |_, _| true,
+                &mut cost,
+                &mut parents,
+            );
+            paths.push(
+                odd.iter()
+                    .map(|b| walk_backwards(&a, b, &parents))
+                    .collect(),
+            );
+        }
+
+        // Minimum weight perfect matching as a maximum weight matching with inverted weights
+        let mut complete = AdjacencyList::with_capacity(odd.len());
+        for _ in &odd {
+            complete.add_node();
+        }
+        let longest = paths
+            .iter()
+            .flatten()
+            .flatten()
+            .map(crate::path_cost)
+            .fold(0.0, Weight::max);
+        for (i, row) in paths.iter().enumerate() {
+            for (j, path) in row.iter().enumerate().skip(i + 1) {
+                if let Some(path) = path {
+                    complete.add_edge(Node(i), Node(j), longest + 1.0 - crate::path_cost(path));
+                }
+            }
+        }
+        let mates = max_weight_matching(&complete, true);
+        for (i, row) in paths.iter().enumerate() {
+            let Some(&j) = mates.get(&Node(i)) else {
+                return Err(EulerError::Disconnected);
+            };
+            if i < j.0 {
+                let path = row[j.0].as_ref().expect("Matched nodes are connected");
+                for w in path.windows(2) {
+                    edges.push((w[0].node, w[1].node, w[1].weight));
+                }
+            }
+        }
+    }
+
+    hierholzer(g.len(), &edges, false, true)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{path_cost, AdjacencyList, EulerError, Node, Path};
+
+    fn graph(len: usize, edges: &[(usize, usize, f32)], undirected: bool) -> AdjacencyList {
+        let mut g = AdjacencyList::new();
+        let n: Vec<Node> = (0..len).map(|_| g.add_node()).collect();
+        for &(a, b, w) in edges {
+            g.add_edge(n[a], n[b], w);
+            if undirected {
+                g.add_edge(n[b], n[a], w);
+            }
+        }
+        g
+    }
+
+    /// Every step follows an edge of g with the right weight, and every edge of g is used
+    fn check_covers(g: &AdjacencyList, path: &Path, undirected: bool) {
+        assert_eq!(path[0].weight, 0.0);
+        for w in path.windows(2) {
+            let e = g.edges(w[0].node).find(|e| e.node == w[1].node).unwrap();
+            assert_eq!(e.weight, w[1].weight);
+        }
+        for n in g.nodes() {
+            for e in g.edges(n) {
+                assert!(path.windows(2).any(|w| {
+                    (w[0].node, w[1].node) == (n, e.node)
+                        || (undirected && (w[1].node, w[0].node) == (n, e.node))
+                }));
+            }
+        }
+    }
+
+    #[test]
+    fn directed() {
+        let g = graph(
+            4,
+            &[
+                (0, 1, 1.0),
+                (1, 2, 2.0),
+                (2, 0, 3.0),
+                (0, 3, 4.0),
+                (3, 0, 5.0),
+            ],
+            false,
+        );
+        let circuit = crate::euler_circuit(&g).unwrap();
+        assert_eq!(circuit.len(), 6);
+        assert_eq!(circuit.first().unwrap().node, circuit.last().unwrap().node);
+        check_covers(&g, &circuit, false);
+        assert_eq!(path_cost(&circuit), 15.0);
+
+        let mut g = g;
+        g.remove_edge(Node(3), Node(0));
+        assert_eq!(crate::euler_circuit(&g).err(), Some(EulerError::Unbalanced));
+        let path = crate::euler_path(&g).unwrap();
+        assert_eq!(path[0].node, Node(0));
+        assert_eq!(path.last().unwrap().node, Node(3));
+        check_covers(&g, &path, false);
+
+        g.add_edge(Node(1), Node(3), 1.0);
+        assert_eq!(crate::euler_path(&g).err(), Some(EulerError::Unbalanced));
+    }
+
+    #[test]
+    fn undirected() {
+        // The house of Nikolaus, two odd nodes
+        let g = graph(
+            5,
+            &[
+                (0, 1, 1.0),
+                (1, 2, 1.0),
+                (2, 3, 1.0),
+                (3, 0, 1.0),
+                (0, 2, 1.0),
+                (1, 3, 1.0),
+                (2, 4, 1.0),
+                (3, 4, 1.0),
+            ],
+            true,
+        );
+        let path = crate::undirected_euler_path(&g).unwrap();
+        assert_eq!(path.len(), 9);
+        assert!(path[0].node == Node(0) || path[0].node == Node(1));
+        check_covers(&g, &path, true);
+        assert_eq!(
+            crate::undirected_euler_circuit(&g).err(),
+            Some(EulerError::Unbalanced)
+        );
+
+        let two_triangles = graph(
+            6,
+            &[
+                (0, 1, 1.0),
+                (1, 2, 1.0),
+                (2, 0, 1.0),
+                (3, 4, 1.0),
+                (4, 5, 1.0),
+                (5, 3, 1.0),
+            ],
+            true,
+        );
+        assert_eq!(
+            crate::undirected_euler_circuit(&two_triangles).err(),
+            Some(EulerError::Disconnected)
+        );
+    }
+
+    #[test]
+    fn directed_postman() {
+        // 0 -> 1 -> 2 -> 0 plus the shortcut 0 -> 2, which forces walking 2 -> 0 twice
+        let g = graph(
+            3,
+            &[(0, 1, 1.0), (1, 2, 1.0), (2, 0, 2.0), (0, 2, 5.0)],
+            false,
+        );
+        let tour = crate::chinese_postman(&g).unwrap();
+        check_covers(&g, &tour, false);
+        assert_eq!(tour.first().unwrap().node, tour.last().unwrap().node);
+        assert_eq!(path_cost(&tour), 9.0 + 2.0);
+
+        let one_way = graph(2, &[(0, 1, 1.0)], false);
+        assert_eq!(
+            crate::chinese_postman(&one_way).err(),
+            Some(EulerError::Disconnected)
+        );
+    }
+
+    #[test]
+    fn undirected_postman() {
+        // A square 0-1-2-3 with a diagonal 0-2 and a tail 2-4. Odd nodes are 0 and 4, the
+        // cheapest way between them is 0-1-2-4.
+        let g = graph(
+            5,
+            &[
+                (0, 1, 1.0),
+                (1, 2, 1.0),
+                (2, 3, 1.0),
+                (3, 0, 1.0),
+                (0, 2, 3.0),
+                (2, 4, 2.0),
+            ],
+            true,
+        );
+        let tour = crate::undirected_chinese_postman(&g).unwrap();
+        check_covers(&g, &tour, true);
+        assert_eq!(tour.first().unwrap().node, tour.last().unwrap().node);
+        assert_eq!(path_cost(&tour), 9.0 + 4.0);
+
+        // K4 where every node is odd, pairing up the two cheap edges is best
+        let g = graph(
+            4,
+            &[
+                (0, 1, 1.0),
+                (2, 3, 1.0),
+                (0, 2, 5.0),
+                (1, 3, 5.0),
+                (0, 3, 4.0),
+                (1, 2, 4.0),
+            ],
+            true,
+        );
+        let tour = crate::undirected_chinese_postman(&g).unwrap();
+        check_covers(&g, &tour, true);
+        assert_eq!(path_cost(&tour), 20.0 + 2.0);
+    }
+}
@@ -0,0 +1,408 @@
+use crate::{AdjacencyList, Node, NodeSet, Weight};
+
+use std::collections::VecDeque;
+
+/// Flow and capacity below this are treated as zero
+pub(crate) const EPS: Weight = 1e-6;
+
+/// The result of a maximum flow computation
+pub struct MaxFlow {
+    pub value: Weight,
+    /// The flow along each edge of the input graph, edges without flow are left out
+    pub flow: AdjacencyList,
+    /// The source side of a minimum cut, the nodes that can still be reached from the source in
+    /// the residual graph. The cut edges are the edges from this set to the rest of the graph.
+    pub source_side: NodeSet,
+}
+
+/// Residual graph where edge 2 * i is the i:th edge of the input and 2 * i + 1 its reverse
+struct Network {
+    to: Vec<Node>,
+    cap: Vec<Weight>,
+    adj: Vec<Vec<usize>>,
+    original: Vec<(Node, Weight)>,
+}
+
+impl Network {
+    fn new(g: &AdjacencyList) -> Self {
+        let mut net = Self {
+            to: Vec::new(),
+            cap: Vec::new(),
+            adj: vec![Vec::new(); g.len()],
+            original: Vec::new(),
+        };
+        for n in g.nodes() {
+            for e in g.edges(n) {
+                assert!(e.weight >= 0.0, "Negative capacity");
+                net.adj[n.0].push(net.to.len());
+                net.to.push(e.node);
+                net.cap.push(e.weight);
+                net.adj[e.node.0].push(net.to.len());
+                net.to.push(n);
+                net.cap.push(0.0);
+                net.original.push((n, e.weight));
+            }
+        }
+        net
+    }
+
+    fn len(&self) -> usize {
+        self.adj.len()
+    }
+
+    fn push(&mut self, e: usize, amount: Weight) {
+        self.cap[e] -= amount;
+        self.cap[e ^ 1] += amount;
+    }
+
+    /// Breadth first distances from `start` through edges with capacity left
+    fn levels(&self, start: Node, level: &mut [usize]) {
+        level.iter_mut().for_each(|l| *l = usize::MAX);
+        let mut queue = VecDeque::new();
+        level[start.0] = 0;
+        queue.push_back(start);
+        while let Some(n) = queue.pop_front() {
+            for &e in &self.adj[n.0] {
+                let child = self.to[e];
+                if self.cap[e] > EPS && level[child.0] == usize::MAX {
+                    level[child.0] = level[n.0] + 1;
+                    queue.push_back(child);
+                }
+            }
+        }
+    }
+
+    fn into_result(self, source: Node, value: Weight) -> MaxFlow {
+        let mut level = vec![usize::MAX; self.len()];
+        self.levels(source, &mut level);
+        let mut source_side = NodeSet::with_capacity(self.len());
+        for (i, &l) in level.iter().enumerate() {
+            if l != usize::MAX {
+                source_side.add(Node(i));
+            }
+        }
+
+        let mut flow = AdjacencyList::with_capacity(self.len());
+        for _ in 0..self.len() {
+            flow.add_node();
+        }
+        for (i, &(from, capacity)) in self.original.iter().enumerate() {
+            let f = capacity - self.cap[2 * i];
+            if f > EPS {
+                flow.add_edge(from, self.to[2 * i], f);
+            }
+        }
+
+        MaxFlow {
+            value,
+            flow,
+            source_side,
+        }
+    }
+}
+
+/// Maximum flow from source to sink with Dinic's algorithm, edge weights are capacities
+pub fn dinic(g: &AdjacencyList, source: Node, sink: Node) -> MaxFlow {
+    assert!(source != sink, "Source and sink must differ");
+    let mut net = Network::new(g);
+    let mut level = vec![usize::MAX; net.len()];
+    let mut next_edge = vec![0; net.len()];
+    let mut path: Vec<usize> = Vec::new();
+    let mut value = 0.0;
+
+    loop {
+        net.levels(source, &mut level);
+        if level[sink.0] == usize::MAX {
+            break;
+        }
+        next_edge.iter_mut().for_each(|i| *i = 0);
+        path.clear();
+
+        // Iterative blocking flow search, `path` holds the edges from source to the current node
+        loop {
+            let cur = path.last().map_or(source, |&e| net.to[e]);
+            if cur == sink {
+                let bottleneck = path
+                    .iter()
+                    .map(|&e| net.cap[e])
+                    .fold(Weight::INFINITY, Weight::min);
+                for &e in &path {
+                    net.push(e, bottleneck);
+                }
+                value += bottleneck;
+                // Retreat to the tail of the first saturated edge
+                let saturated = path
+                    .iter()
+                    .position(|&e| net.cap[e] <= EPS)
+                    .expect("Bottleneck edge is saturated");
+                path.truncate(saturated);
+                continue;
+            }
+
+            let admissible = net.adj[cur.0][next_edge[cur.0]..]
+                .iter()
+                .position(|&e| net.cap[e] > EPS && level[net.to[e].0] == level[cur.0] + 1);
+            match admissible {
+                Some(offset) => {
+                    next_edge[cur.0] += offset;
+                    path.push(net.adj[cur.0][next_edge[cur.0]]);
+                }
+                None => {
+                    // Dead end, never come back here in this phase
+                    next_edge[cur.0] = net.adj[cur.0].len();
+                    level[cur.0] = usize::MAX;
+                    if path.pop().is_none() {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    net.into_result(source, value)
+}
+
+/// Maximum flow from source to sink with the highest-label push-relabel algorithm, using the gap
+/// heuristic. Edge weights are capacities.
+pub fn push_relabel(g: &AdjacencyList, source: Node, sink: Node) -> MaxFlow {
+    assert!(source != sink, "Source and sink must differ");
+    let mut net = Network::new(g);
+    let len = net.len();
+    let mut height = vec![0; len];
+    let mut excess: Vec<Weight> = vec![0.0; len];
+    let mut next_edge = vec![0; len];
+    // Number of nodes at each height, used for the gap heuristic
+    let mut count = vec![0; 2 * len + 1];
+    let mut buckets: Vec<Vec<Node>> = vec![Vec::new(); 2 * len + 1];
+    let mut highest = 0;
+
+    height[source.0] = len;
+    count[0] = len - 1;
+    count[len] = 1;
+
+    for i in 0..net.adj[source.0].len() {
+        let e = net.adj[source.0][i];
+        let amount = net.cap[e];
+        if amount > EPS {
+            let child = net.to[e];
+            net.push(e, amount);
+            excess[source.0] -= amount;
+            if child != sink && excess[child.0] <= EPS {
+                buckets[0].push(child);
+            }
+            excess[child.0] += amount;
+        }
+    }
+
+    loop {
+        while highest > 0 && buckets[highest].is_empty() {
+            highest -= 1;
+        }
+        let Some(n) = buckets[highest].pop() else {
+            break;
+        };
+        if height[n.0] != highest || excess[n.0] <= EPS {
+            continue;
+        }
+
+        // Discharge n
+        while excess[n.0] > EPS {
+            if next_edge[n.0] == net.adj[n.0].len() {
+                let old = height[n.0];
+                let new = net.adj[n.0]
+                    .iter()
+                    .filter(|&&e| net.cap[e] > EPS)
+                    .map(|&e| height[net.to[e].0] + 1)
+                    .min()
+                    .filter(|&h| h < 2 * len);
+                let Some(new) = new else {
+                    // Heights stay below 2 * len with exact arithmetic. Getting here means the
+                    // excess is float drift around EPS that can't be pushed anywhere, drop it.
+                    excess[n.0] = 0.0;
+                    break;
+                };
+                count[old] -= 1;
+                height[n.0] = new;
+                count[new] += 1;
+                next_edge[n.0] = 0;
+
+                if count[old] == 0 && old < len {
+                    // Gap, nothing above it can reach the sink anymore
+                    for m in 0..len {
+                        let h = height[m];
+                        if h > old && h < len && m != source.0 {
+                            count[h] -= 1;
+                            height[m] = len + 1;
+                            count[len + 1] += 1;
+                            next_edge[m] = 0;
+                            if excess[m] > EPS && m != sink.0 {
+                                buckets[len + 1].push(Node(m));
+                            }
+                        }
+                    }
+                    highest = highest.max(len + 1);
+                }
+                continue;
+            }
+
+            let e = net.adj[n.0][next_edge[n.0]];
+            let child = net.to[e];
+            if net.cap[e] > EPS && height[n.0] == height[child.0] + 1 {
+                let amount = excess[n.0].min(net.cap[e]);
+                net.push(e, amount);
+                excess[n.0] -= amount;
+                if child != source && child != sink && excess[child.0] <= EPS {
+                    buckets[height[child.0]].push(child);
+                }
+                excess[child.0] += amount;
+            } else {
+                next_edge[n.0] += 1;
+            }
+        }
+
+        if height[n.0] > highest {
+            highest = height[n.0];
+        }
+    }
+
+    let value = excess[sink.0];
+    net.into_result(source, value)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{AdjacencyList, MaxFlow, Node};
+
+    // The example from CLRS, max flow 23
+    fn example() -> AdjacencyList {
+        let mut g = AdjacencyList::new();
+        let n: Vec<Node> = (0..6).map(|_| g.add_node()).collect();
+        for (a, b, c) in [
+            (0, 1, 16.0),
+            (0, 2, 13.0),
+            (2, 1, 4.0),
+            (1, 3, 12.0),
+            (3, 2, 9.0),
+            (2, 4, 14.0),
+            (4, 3, 7.0),
+            (3, 5, 20.0),
+            (4, 5, 4.0),
+        ] {
+            g.add_edge(n[a], n[b], c);
+        }
+        g
+    }
+
+    fn check(g: &AdjacencyList, result: &MaxFlow, source: Node, sink: Node, expected: f32) {
+        // f32 keeps about seven digits, so large flows get a relative tolerance
+        let tolerance = 1e-4 * expected.max(1.0);
+        assert!((result.value - expected).abs() < tolerance);
+
+        // Capacity and conservation
+        let mut balance = vec![0.0f32; g.len()];
+        for n in result.flow.nodes() {
+            for e in result.flow.edges(n) {
+                let cap = g.edges(n).find(|x| x.node == e.node).unwrap().weight;
+                assert!(e.weight <= cap + tolerance);
+                balance[n.0] -= e.weight;
+                balance[e.node.0] += e.weight;
+            }
+        }
+        for n in g.nodes() {
+            let expected = if n == source {
+                -expected
+            } else if n == sink {
+                expected
+            } else {
+                0.0
+            };
+            assert!((balance[n.0] - expected).abs() < tolerance);
+        }
+
+        // The cut has the same capacity as the flow
+        assert!(result.source_side.has(source));
+        assert!(!result.source_side.has(sink));
+        let mut cut = 0.0;
+        for n in g.nodes().filter(|&n| result.source_side.has(n)) {
+            for e in g.edges(n).filter(|e| !result.source_side.has(e.node)) {
+                cut += e.weight;
+            }
+        }
+        assert!((cut - expected).abs() < tolerance);
+    }
+
+    #[test]
+    fn dinic() {
+        let g = example();
+        let result = super::dinic(&g, Node(0), Node(5));
+        check(&g, &result, Node(0), Node(5), 23.0);
+    }
+
+    #[test]
+    fn push_relabel() {
+        let g = example();
+        let result = super::push_relabel(&g, Node(0), Node(5));
+        check(&g, &result, Node(0), Node(5), 23.0);
+    }
+
+    #[test]
+    fn grid() {
+        let grid = crate::Grid::new(6, 5, |(x, y)| x != 3 || y == 2);
+        let g = grid.to_graph();
+        let (s, t) = (grid.node((0, 0)), grid.node((5, 4)));
+        for result in [super::dinic(&g, s, t), super::push_relabel(&g, s, t)] {
+            check(&g, &result, s, t, 1.0);
+        }
+        let (s, t) = (grid.node((0, 2)), grid.node((2, 2)));
+        let a = super::dinic(&g, s, t);
+        let b = super::push_relabel(&g, s, t);
+        assert!((a.value - b.value).abs() < 1e-4);
+        check(&g, &b, s, t, a.value);
+    }
+
+    #[test]
+    fn disconnected() {
+        let mut g = example();
+        let lonely = g.add_node();
+        for result in [
+            super::dinic(&g, Node(0), lonely),
+            super::push_relabel(&g, Node(0), lonely),
+        ] {
+            check(&g, &result, Node(0), lonely, 0.0);
+        }
+    }
+
+    #[test]
+    fn random_graphs_agree() {
+        let mut rand = crate::test_rng(0x2545f4914f6cdd1d);
+
+        // Integer capacities, then fractional ones large enough that float drift leaves excesses
+        // just above EPS
+        for round in 0..1020 {
+            let fractional = round >= 20;
+            let len = if fractional {
+                5 + rand(11) as usize
+            } else {
+                12
+            };
+            let mut g = AdjacencyList::new();
+            let n: Vec<Node> = (0..len).map(|_| g.add_node()).collect();
+            for _ in 0..if fractional { 60 } else { 40 } {
+                let (a, b) = (rand(len as u64) as usize, rand(len as u64) as usize);
+                let capacity = if fractional {
+                    rand(1000) as f32 / 7.0 + 0.01
+                } else {
+                    rand(10) as f32
+                };
+                if a != b {
+                    g.add_edge(n[a], n[b], capacity);
+                }
+            }
+            let (s, t) = (n[0], n[len - 1]);
+            let a = super::dinic(&g, s, t);
+            let b = super::push_relabel(&g, s, t);
+            check(&g, &a, s, t, a.value);
+            check(&g, &b, s, t, a.value);
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use crate::{multi_source_dijkstra, AdjacencyList, Grid, Node, NodeMap, NodeSet, Weight};
+
+/// A step on the grid, each component is -1, 0 or 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Direction {
+    pub dx: i8,
+    pub dy: i8,
+}
+
+/// Precomputed next-hop for every tile toward the closest of a set of sources.
+///
+/// Built with a single multi-source Dijkstra over the inverted graph so that any number of units
+/// can follow it without further searches.
+pub struct FlowField {
+    distance: NodeMap<Weight>,
+    next: NodeMap<Node>,
+    directions: NodeMap<Direction>,
+}
+
+impl FlowField {
+    /// `g` is expected to be a graph over `grid`, e.g. from `Grid::to_graph`
+    pub fn new(g: &AdjacencyList, grid: &Grid, sources: &[Node]) -> Self {
+        let distance = multi_source_dijkstra(&g.inverted(), sources);
+        let mut next = NodeMap::with_capacity(g.len());
+        let mut directions = NodeMap::with_capacity(g.len());
+        let mut is_source = NodeSet::with_capacity(g.len());
+        is_source.add_many(sources);
+
+        for (n, _) in distance.iter() {
+            if is_source.has(n) {
+                continue;
+            }
+
+            let best = g
+                .edges(n)
+                .filter_map(|e| distance.get(&e.node).map(|d| (e.node, e.weight + d)))
+                .min_by(|a, b| a.1.partial_cmp(&b.1).expect("Invalid float"));
+
+            if let Some((hop, _)) = best {
+                let (x0, y0) = grid.coords(n);
+                let (x1, y1) = grid.coords(hop);
+                next.insert(n, hop);
+                directions.insert(
+                    n,
+                    Direction {
+                        dx: (x1 as isize - x0 as isize).signum() as i8,
+                        dy: (y1 as isize - y0 as isize).signum() as i8,
+                    },
+                );
+            }
+        }
+
+        Self {
+            distance,
+            next,
+            directions,
+        }
+    }
+
+    /// The cost to reach the closest source, None if no source is reachable
+    pub fn distance(&self, n: Node) -> Option<Weight> {
+        self.distance.get(&n).copied()
+    }
+
+    /// The neighbor to move to, None for sources and tiles that can't reach a source
+    pub fn next_hop(&self, n: Node) -> Option<Node> {
+        self.next.get(&n).copied()
+    }
+
+    pub fn direction(&self, n: Node) -> Option<Direction> {
+        self.directions.get(&n).copied()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Direction, FlowField, Grid};
+
+    #[test]
+    fn follow_to_source() {
+        // ....
+        // .##.
+        // ....
+        let grid = Grid::new(4, 3, |c| c != (1, 1) && c != (2, 1));
+        let g = grid.to_graph();
+        let source = grid.node((3, 2));
+        let field = FlowField::new(&g, &grid, &[source]);
+
+        assert_eq!(field.next_hop(source), None);
+        assert_eq!(field.distance(source), Some(0.0));
+        assert_eq!(field.direction(grid.node((1, 1))), None);
+
+        for start in [(0, 0), (0, 2), (3, 0)] {
+            let mut cur = grid.node(start);
+            let mut steps = 0;
+            while let Some(next) = field.next_hop(cur) {
+                cur = next;
+                steps += 1;
+                assert!(steps < grid.len());
+            }
+            assert_eq!(cur, source);
+        }
+
+        assert_eq!(
+            field.direction(grid.node((3, 0))),
+            Some(Direction { dx: 0, dy: 1 })
+        );
+    }
+}
@@ -0,0 +1,214 @@
+use crate::{AdjacencyList, Node, Weight};
+
+pub type Coords = (usize, usize);
+
+pub const STRAIGHT_COST: Weight = 1.0;
+pub const DIAG_COST: Weight = std::f32::consts::SQRT_2;
+
+/// A 2d tile map where every tile is a node.
+///
+/// Nodes are numbered row-major, i.e. the tile (x, y) is `Node(x + y * width)`. This matches a
+/// graph that was built by calling `add_node` for each tile, row by row.
+#[derive(Debug, Clone)]
+pub struct Grid {
+    width: usize,
+    height: usize,
+    passable: Vec<bool>,
+}
+
+impl Grid {
+    pub fn new(width: usize, height: usize, passable: impl Fn(Coords) -> bool) -> Self {
+        let mut v = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                v.push(passable((x, y)));
+            }
+        }
+        Self {
+            width,
+            height,
+            passable: v,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn len(&self) -> usize {
+        self.passable.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.passable.is_empty()
+    }
+
+    pub fn in_bounds(&self, (x, y): Coords) -> bool {
+        x < self.width && y < self.height
+    }
+
+    pub fn node(&self, c: Coords) -> Node {
+        assert!(self.in_bounds(c), "Coords out of bounds: {:?}", c);
+        Node(c.0 + c.1 * self.width)
+    }
+
+    pub fn coords(&self, n: Node) -> Coords {
+        assert!(n.0 < self.len(), "Node not in grid: {}", n);
+        (n.0 % self.width, n.0 / self.width)
+    }
+
+    /// Out of bounds tiles are never passable
+    pub fn is_passable(&self, c: Coords) -> bool {
+        self.in_bounds(c) && self.passable[c.0 + c.1 * self.width]
+    }
+
+    pub(crate) fn is_passable_signed(&self, x: isize, y: isize) -> bool {
+        x >= 0 && y >= 0 && self.is_passable((x as usize, y as usize))
+    }
+
+    /// The 8-connected neighbors of a passable tile. Diagonal steps are not allowed to cut
+    /// corners, both of the adjacent straight tiles have to be passable.
+    pub fn neighbors(&self, c: Coords) -> impl Iterator<Item = (Coords, Weight)> + '_ {
+        const OFFSETS: [(isize, isize); 8] = [
+            (1, 0),
+            (1, 1),
+            (1, -1),
+            (0, 1),
+            (0, -1),
+            (-1, 0),
+            (-1, -1),
+            (-1, 1),
+        ];
+        let passable = self.is_passable(c);
+        let (x, y) = (c.0 as isize, c.1 as isize);
+        OFFSETS
+            .iter()
+            .filter(move |_| passable)
+            .filter(move |&&(dx, dy)| {
+                self.is_passable_signed(x + dx, y + dy)
+                    && self.is_passable_signed(x + dx, y)
+                    && self.is_passable_signed(x, y + dy)
+            })
+            .map(move |&(dx, dy)| {
+                let cost = if dx != 0 && dy != 0 {
+                    DIAG_COST
+                } else {
+                    STRAIGHT_COST
+                };
+                (((x + dx) as usize, (y + dy) as usize), cost)
+            })
+    }
+
+    /// Check if the straight line between the centers of two tiles only passes through passable
+    /// tiles. Every tile the line touches is checked (supercover) and when the line goes exactly
+    /// through a corner, both tiles sharing that corner have to be passable, same as for diagonal
+    /// steps in `neighbors`.
+    pub fn line_of_sight(&self, a: Coords, b: Coords) -> bool {
+        let (mut x, mut y) = (a.0 as isize, a.1 as isize);
+        let (x1, y1) = (b.0 as isize, b.1 as isize);
+        let (dx, dy) = ((x1 - x).abs(), (y1 - y).abs());
+        let (sx, sy) = ((x1 - x).signum(), (y1 - y).signum());
+
+        if !self.is_passable_signed(x, y) {
+            return false;
+        }
+
+        let mut error = dx - dy;
+        let mut remaining = dx + dy;
+        while remaining > 0 {
+            if error > 0 {
+                x += sx;
+                error -= 2 * dy;
+                remaining -= 1;
+            } else if error < 0 {
+                y += sy;
+                error += 2 * dx;
+                remaining -= 1;
+            } else {
+                if !self.is_passable_signed(x + sx, y) || !self.is_passable_signed(x, y + sy) {
+                    return false;
+                }
+                x += sx;
+                y += sy;
+                error += 2 * (dx - dy);
+                remaining -= 2;
+            }
+
+            if !self.is_passable_signed(x, y) {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Euclidean distance between the centers of two tiles
+    pub fn distance(&self, a: Coords, b: Coords) -> Weight {
+        let x = a.0 as Weight - b.0 as Weight;
+        let y = a.1 as Weight - b.1 as Weight;
+        (x.powi(2) + y.powi(2)).sqrt()
+    }
+
+    /// Build an 8-connected graph with one node per tile, see `neighbors`
+    pub fn to_graph(&self) -> AdjacencyList {
+        let mut g = AdjacencyList::with_capacity(self.len());
+        for _ in 0..self.len() {
+            g.add_node();
+        }
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let n = self.node((x, y));
+                for (c, cost) in self.neighbors((x, y)) {
+                    g.add_edge(n, self.node(c), cost);
+                }
+            }
+        }
+        g
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::Grid;
+
+    #[test]
+    fn node_coords_roundtrip() {
+        let grid = Grid::new(4, 3, |_| true);
+        for y in 0..3 {
+            for x in 0..4 {
+                assert_eq!(grid.coords(grid.node((x, y))), (x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn no_corner_cutting() {
+        // .#
+        // ..
+        let grid = Grid::new(2, 2, |c| c != (1, 0));
+        let g = grid.to_graph();
+        assert!(!g.has_edge(grid.node((0, 0)), grid.node((1, 1))));
+        assert!(g.has_edge(grid.node((0, 0)), grid.node((0, 1))));
+        assert_eq!(g.edges(grid.node((1, 0))).count(), 0);
+    }
+
+    #[test]
+    fn line_of_sight() {
+        // ....
+        // .#..
+        // ....
+        let grid = Grid::new(4, 3, |c| c != (1, 1));
+        assert!(grid.line_of_sight((0, 0), (3, 0)));
+        assert!(grid.line_of_sight((3, 2), (2, 0)));
+        assert!(!grid.line_of_sight((0, 1), (3, 1)));
+        assert!(!grid.line_of_sight((0, 0), (2, 2)));
+        assert!(!grid.line_of_sight((2, 2), (0, 0)));
+        // Passing exactly through a corner of the wall
+        assert!(!grid.line_of_sight((1, 0), (3, 2)));
+        assert!(!grid.line_of_sight((0, 2), (3, 1)));
+    }
+}
@@ -1,19 +1,83 @@
-use std::cmp::Ordering;
-use std::collections::VecDeque;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, VecDeque};
 
+mod anyangle;
 mod astar;
+mod biconnected;
+mod bipartite;
+mod blossom;
+mod centrality;
+mod closure;
+mod critical;
+mod dijkstra;
+mod dominators;
+mod dynamic_topsort;
+mod euler;
+mod flow;
+mod flowfield;
 mod fmt;
+mod grid;
 mod map;
+mod mapf;
+mod mincost;
+mod mst;
+mod reachability;
+mod scc;
+mod scheduler;
 mod set;
-
+mod smoothing;
+mod steiner;
+mod traversal;
+mod tsp;
+mod unionfind;
+mod visit;
+mod yen;
+
+pub use anyangle::{lazy_theta_star, theta_star};
 pub use astar::{a_star, AStarAcceleration, HeuristicDistance};
+pub use biconnected::{
+    articulation_points, biconnected_components, bridges, BiconnectedComponents, BlockCutTree,
+};
+pub use bipartite::{hopcroft_karp, hungarian, Assignment};
+pub use blossom::{matching_weight, max_weight_matching, maximum_matching};
+pub use centrality::{betweenness, closeness, harmonic, pagerank, PageRankOptions};
+pub use closure::{transitive_closure, transitive_reduction};
+pub use critical::{critical_path, longest_path, Schedule};
+pub use dijkstra::{multi_source_dijkstra, shortest_path};
+pub use dominators::{dominance_frontiers, dominators, post_dominators};
+pub use dynamic_topsort::DynamicTopsort;
+pub use euler::{
+    chinese_postman, euler_circuit, euler_path, undirected_chinese_postman,
+    undirected_euler_circuit, undirected_euler_path, EulerError,
+};
+pub use flow::{dinic, push_relabel, MaxFlow};
+pub use flowfield::{Direction, FlowField};
+pub use grid::{Coords, Grid, DIAG_COST, STRAIGHT_COST};
 pub use map::NodeMap;
+pub use mapf::{conflict_based_search, space_time_a_star, ReservationTable};
+pub use mincost::{min_cost_flow, MinCostFlow, MinCostFlowError};
+pub use mst::{boruvka, kruskal, prim, SpanningForest};
+pub use reachability::Reachability;
+pub use scc::strongly_connected_components;
+pub use scheduler::{run_parallel, TaskResult};
 pub use set::NodeSet;
+pub use smoothing::{funnel, string_pull, Point};
+pub use steiner::steiner_tree;
+pub use traversal::{Bfs, Dfs};
+pub use tsp::{tsp, Tour};
+pub use unionfind::{connected_components, NodeUnionFind};
+pub use visit::{dfs_visit, Control, DfsTimes, DfsVisitor};
+pub use yen::k_shortest_paths;
 
 pub use fmt::to_dot;
 
 pub type Path = Vec<Edge>;
 
+/// Sum of the edge weights along a path
+pub fn path_cost(path: &Path) -> Weight {
+    path.iter().map(|e| e.weight).sum()
+}
+
 // TODO: u32
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Node(usize);
@@ -56,7 +120,7 @@ impl From<(Node, Weight)> for Edge {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct AdjacencyList {
     nodes: Vec<Vec<Edge>>,
 }
@@ -136,6 +200,8 @@ impl AdjacencyList {
         self.nodes.is_empty()
     }
 
+    /// The graph with every edge reversed. Edges keep their weights, callers that want the
+    /// structure only should not rely on them being 1.
     pub fn inverted(&self) -> Self {
         let mut out = Self::new();
         out.nodes.reserve(self.nodes.len());
@@ -144,13 +210,46 @@ impl AdjacencyList {
         }
         for n in self.nodes() {
             for e in self.edges(n) {
-                out.add_edge(e.node, n, 1.0);
+                out.add_edge(e.node, n, e.weight);
             }
         }
         out
     }
 }
 
+/// All edges of g as unordered pairs (a, b) with a < b, sorted. If both directions exist the
+/// smallest weight is kept. Self loops are skipped.
+pub(crate) fn undirected_edges(g: &AdjacencyList) -> Vec<(Node, Node, Weight)> {
+    let mut out: Vec<(Node, Node, Weight)> = Vec::new();
+    for n in g.nodes() {
+        for e in g.edges(n) {
+            if e.node != n {
+                out.push((n.min(e.node), n.max(e.node), e.weight));
+            }
+        }
+    }
+    out.sort_by(|x, y| {
+        (x.0, x.1)
+            .cmp(&(y.0, y.1))
+            .then_with(|| x.2.partial_cmp(&y.2).expect("Invalid float"))
+    });
+    out.dedup_by(|x, y| (x.0, x.1) == (y.0, y.1));
+    out
+}
+
+/// A graph with `len` nodes where every edge is added in both directions
+pub(crate) fn undirected_graph(len: usize, edges: &[(Node, Node, Weight)]) -> AdjacencyList {
+    let mut g = AdjacencyList::with_capacity(len);
+    for _ in 0..len {
+        g.add_node();
+    }
+    for &(a, b, w) in edges {
+        g.add_edge(a, b, w);
+        g.add_edge(b, a, w);
+    }
+    g
+}
+
 pub fn compute_roots(g: &AdjacencyList) -> Vec<Node> {
     let mut roots: NodeSet = NodeSet::with_capacity(g.len());
     for n in g.nodes() {
@@ -166,42 +265,16 @@ pub fn compute_roots(g: &AdjacencyList) -> Vec<Node> {
     roots.to_vec()
 }
 
-fn dfs_at_impl(
-    g: &AdjacencyList,
-    n: Node,
-    mut visit: impl FnMut(Node),
-    queue: &mut VecDeque<Node>,
-    visited: &mut NodeSet,
-) {
-    queue.clear();
-    queue.push_back(n);
-    while let Some(n) = queue.pop_back() {
-        let new = visited.add(n);
-        if !new {
-            continue;
-        }
-        visit(n);
-        for &Edge { node: child, .. } in g.edges(n) {
-            queue.push_back(child);
+pub fn dfs(g: &AdjacencyList, visit: impl FnMut(Node)) {
+    struct Discover<F>(F);
+    impl<F: FnMut(Node)> DfsVisitor for Discover<F> {
+        fn discover(&mut self, n: Node, _time: usize) -> Control {
+            (self.0)(n);
+            Control::Continue
         }
     }
-}
-
-pub fn dfs(g: &AdjacencyList, mut visit: impl FnMut(Node)) {
-    if g.nodes.is_empty() {
-        return;
-    }
-
-    let mut queue = VecDeque::new();
-    let mut visited = NodeSet::new();
 
-    for n in g.nodes() {
-        if visited.has(n) {
-            continue;
-        }
-
-        dfs_at_impl(g, n, &mut visit, &mut queue, &mut visited);
-    }
+    dfs_visit(g, g.nodes(), &mut Discover(visit));
 }
 
 pub fn bfs(g: &AdjacencyList, mut visit: impl FnMut(Node)) {
@@ -249,6 +322,28 @@ fn walk_backwards(start: &Node, end: &Node, parents: &NodeMap<Edge>) -> Option<P
     }
 }
 
+/// The graph has a cycle but the algorithm needs a DAG
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CycleError;
+
+impl std::fmt::Display for CycleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Graph has a cycle")
+    }
+}
+
+impl std::error::Error for CycleError {}
+
+/// `topsort` that fails instead of leaving out the nodes on or after a cycle
+pub(crate) fn topsort_dag(g: &AdjacencyList) -> Result<Vec<Node>, CycleError> {
+    let order = topsort(g);
+    if order.len() == g.len() {
+        Ok(order)
+    } else {
+        Err(CycleError)
+    }
+}
+
 pub fn topsort(g: &AdjacencyList) -> Vec<Node> {
     let mut out = vec![];
 
@@ -274,9 +369,92 @@ pub fn topsort(g: &AdjacencyList) -> Vec<Node> {
     out
 }
 
+/// Number of incoming edges for every node
+fn in_degrees(g: &AdjacencyList) -> Vec<usize> {
+    let mut incoming = vec![0; g.len()];
+    for n in g.nodes() {
+        for e in g.edges(n) {
+            incoming[e.node.0] += 1;
+        }
+    }
+    incoming
+}
+
+/// Topological sort that always picks the available node with the smallest key next, ties are
+/// broken by the smallest node. With `|n| n` as key this is the lexicographically smallest order.
+pub fn topsort_by_key<K: Ord>(
+    g: &AdjacencyList,
+    key: impl Fn(Node) -> K,
+) -> Result<Vec<Node>, CycleError> {
+    let mut incoming = in_degrees(g);
+    let mut available: BinaryHeap<Reverse<(K, Node)>> = g
+        .nodes()
+        .filter(|n| incoming[n.0] == 0)
+        .map(|n| Reverse((key(n), n)))
+        .collect();
+
+    let mut out = Vec::with_capacity(g.len());
+    while let Some(Reverse((_, n))) = available.pop() {
+        out.push(n);
+        for e in g.edges(n) {
+            incoming[e.node.0] -= 1;
+            if incoming[e.node.0] == 0 {
+                available.push(Reverse((key(e.node), e.node)));
+            }
+        }
+    }
+
+    if out.len() == g.len() {
+        Ok(out)
+    } else {
+        Err(CycleError)
+    }
+}
+
+/// Group the nodes of a DAG by depth: the first layer has the nodes without incoming edges and
+/// every other node is in the layer after its deepest predecessor. Nodes in a layer don't depend
+/// on each other and are sorted.
+pub fn topsort_layers(g: &AdjacencyList) -> Result<Vec<Vec<Node>>, CycleError> {
+    let mut incoming = in_degrees(g);
+    let mut layer: Vec<Node> = g.nodes().filter(|n| incoming[n.0] == 0).collect();
+    let mut layers = Vec::new();
+    let mut count = 0;
+    while !layer.is_empty() {
+        let mut next = Vec::new();
+        for &n in &layer {
+            for e in g.edges(n) {
+                incoming[e.node.0] -= 1;
+                if incoming[e.node.0] == 0 {
+                    next.push(e.node);
+                }
+            }
+        }
+        next.sort();
+        count += layer.len();
+        layers.push(std::mem::replace(&mut layer, next));
+    }
+
+    if count == g.len() {
+        Ok(layers)
+    } else {
+        Err(CycleError)
+    }
+}
+
+/// Deterministic xorshift generator for randomized tests, each call returns a value below `max`
+#[cfg(test)]
+pub(crate) fn test_rng(mut seed: u64) -> impl FnMut(u64) -> u64 {
+    move |max| {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        seed % max
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use crate::{AdjacencyList, Node};
+    use crate::{edge, AdjacencyList, Node};
 
     fn example_graph_trivial() -> AdjacencyList {
         let mut g = AdjacencyList::new();
@@ -393,6 +571,18 @@ mod test {
         }
     }
 
+    #[test]
+    fn inverted_keeps_weights() {
+        let mut g = AdjacencyList::new();
+        let n: Vec<Node> = (0..3).map(|_| g.add_node()).collect();
+        g.add_edge(n[0], n[1], 2.5);
+        g.add_edge(n[1], n[2], 4.0);
+        let inv = g.inverted();
+        assert_eq!(inv.edges(n[1]).collect::<Vec<_>>(), [&edge(n[0], 2.5)]);
+        assert_eq!(inv.edges(n[2]).collect::<Vec<_>>(), [&edge(n[1], 4.0)]);
+        assert_eq!(inv.edges(n[0]).count(), 0);
+    }
+
     #[test]
     fn dfs() {
         let g = graph_from(example_edges_cyclic);
@@ -453,4 +643,41 @@ mod test {
         let out = super::topsort(&g);
         check_ordering("topsort_dag_example", &g, &out, expected);
     }
+
+    #[test]
+    fn topsort_by_key() {
+        let g = graph_from(example_edges_dag);
+        let out = super::topsort_by_key(&g, |n| n).unwrap();
+        let expected = &[0, 1, 2, 3, 4, 5, 6, 7, 8, 10, 11, 12, 13, 14, 9, 15, 16, 17];
+        check_ordering("topsort_by_key_lexicographic", &g, &out, expected);
+
+        // Largest node first
+        let g = init(&[(0, 1), (2, 1), (1, 3), (4, 3)]);
+        let out = super::topsort_by_key(&g, std::cmp::Reverse).unwrap();
+        check_ordering("topsort_by_key_reverse", &g, &out, &[4, 2, 0, 1, 3]);
+
+        let cyclic = graph_from(example_edges_cyclic);
+        assert_eq!(
+            super::topsort_by_key(&cyclic, |n| n).err(),
+            Some(super::CycleError)
+        );
+    }
+
+    #[test]
+    fn topsort_layers() {
+        let g = init(&[(0, 1), (2, 1), (1, 3), (4, 3), (0, 3)]);
+        let layers = super::topsort_layers(&g).unwrap();
+        let expected: Vec<Vec<Node>> = vec![
+            vec![Node(0), Node(2), Node(4)],
+            vec![Node(1)],
+            vec![Node(3)],
+        ];
+        assert_eq!(layers, expected);
+
+        let cyclic = graph_from(example_edges_cyclic);
+        assert_eq!(
+            super::topsort_layers(&cyclic).err(),
+            Some(super::CycleError)
+        );
+    }
 }
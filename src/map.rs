@@ -1,6 +1,6 @@
 use crate::Node;
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone)]
 pub struct NodeMap<T> {
     v: Vec<Option<T>>,
 }
@@ -82,4 +82,12 @@ impl<T> NodeMap<T> {
     pub fn clear(&mut self) {
         self.v.clear();
     }
+
+    /// Iterate over all nodes in the map and their values, in node order
+    pub fn iter(&self) -> impl Iterator<Item = (Node, &T)> {
+        self.v
+            .iter()
+            .enumerate()
+            .filter_map(|(i, t)| t.as_ref().map(|t| (Node(i), t)))
+    }
 }
@@ -0,0 +1,426 @@
+use crate::{edge, AdjacencyList, Edge, Node, NodeMap, Path};
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+
+/// Time steps that are blocked for an agent.
+///
+/// Used both as the reservation table for planning agents one by one and as the set of
+/// constraints for a single agent in conflict-based search.
+#[derive(Debug, Clone, Default)]
+pub struct ReservationTable {
+    vertices: HashSet<(Node, usize)>,
+    edges: HashSet<(Node, Node, usize)>,
+    parked: NodeMap<usize>,
+    horizon: usize,
+}
+
+impl ReservationTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Block being at n at time t
+    pub fn reserve_vertex(&mut self, n: Node, t: usize) {
+        self.vertices.insert((n, t));
+        self.horizon = self.horizon.max(t);
+    }
+
+    /// Block moving from a at time t to b at time t + 1
+    pub fn reserve_edge(&mut self, a: Node, b: Node, t: usize) {
+        self.edges.insert((a, b, t));
+        self.horizon = self.horizon.max(t + 1);
+    }
+
+    /// Block n from time t and onwards, e.g. for an agent that has reached its goal
+    pub fn reserve_from(&mut self, n: Node, t: usize) {
+        let t = self.parked.get(&n).map_or(t, |&prev| prev.min(t));
+        self.parked.insert(n, t);
+        self.horizon = self.horizon.max(t);
+    }
+
+    /// Reserve everything another agent needs to follow `path` without collisions: the nodes
+    /// along it, the reversed moves to avoid swaps and the goal after the agent has arrived.
+    pub fn reserve_path(&mut self, path: &Path) {
+        for (t, e) in path.iter().enumerate() {
+            self.reserve_vertex(e.node, t);
+        }
+        for (t, w) in path.windows(2).enumerate() {
+            if w[0].node != w[1].node {
+                self.reserve_edge(w[1].node, w[0].node, t);
+            }
+        }
+        if let Some(last) = path.last() {
+            self.reserve_from(last.node, path.len() - 1);
+        }
+    }
+
+    pub fn is_vertex_free(&self, n: Node, t: usize) -> bool {
+        !self.vertices.contains(&(n, t)) && self.parked.get(&n).is_none_or(|&from| t < from)
+    }
+
+    pub fn is_edge_free(&self, a: Node, b: Node, t: usize) -> bool {
+        !self.edges.contains(&(a, b, t))
+    }
+
+    /// The earliest time an agent can stop at n and stay there forever
+    fn earliest_stop(&self, n: Node) -> Option<usize> {
+        if self.parked.has(&n) {
+            return None;
+        }
+        let last = self
+            .vertices
+            .iter()
+            .filter(|(v, _)| *v == n)
+            .map(|&(_, t)| t + 1)
+            .max();
+        Some(last.unwrap_or(0))
+    }
+}
+
+/// Number of edges on the shortest path to goal, for every node that can reach it
+fn hop_distances(inverted: &AdjacencyList, goal: Node) -> NodeMap<usize> {
+    let mut dist = NodeMap::with_capacity(inverted.len());
+    let mut queue = VecDeque::new();
+    dist.insert(goal, 0);
+    queue.push_back(goal);
+    while let Some(n) = queue.pop_front() {
+        let d = dist[n];
+        for &Edge { node: child, .. } in inverted.edges(n) {
+            if !dist.has(&child) {
+                dist.insert(child, d + 1);
+                queue.push_back(child);
+            }
+        }
+    }
+    dist
+}
+
+fn space_time_a_star_impl(
+    g: &AdjacencyList,
+    start: Node,
+    goal: Node,
+    heuristic: &NodeMap<usize>,
+    table: &ReservationTable,
+) -> Option<Path> {
+    let stop = table.earliest_stop(goal)?;
+    let h = |n: Node| heuristic.get(&n).copied();
+    h(start)?;
+    if !table.is_vertex_free(start, 0) {
+        return None;
+    }
+
+    // Waiting out every reservation and then walking the whole graph is always enough
+    let horizon = table.horizon + g.len() + 1;
+
+    let mut queue = BinaryHeap::new();
+    let mut parents: HashMap<(Node, usize), Node> = HashMap::new();
+    let mut closed: HashSet<(Node, usize)> = HashSet::new();
+    queue.push(Reverse((h(start)?, 0, start)));
+
+    while let Some(Reverse((_, t, cur))) = queue.pop() {
+        if !closed.insert((cur, t)) {
+            continue;
+        }
+
+        if cur == goal && t >= stop {
+            let mut nodes = vec![cur];
+            let mut state = (cur, t);
+            while state.1 > 0 {
+                let parent = parents[&state];
+                nodes.push(parent);
+                state = (parent, state.1 - 1);
+            }
+            nodes.reverse();
+            let mut path: Path = nodes.into_iter().map(|n| edge(n, 1.0)).collect();
+            path[0].weight = 0.0;
+            return Some(path);
+        }
+
+        if t + 1 >= horizon {
+            continue;
+        }
+
+        let wait = std::iter::once(cur);
+        let moves = g.edges(cur).map(|e| e.node);
+        for child in wait.chain(moves) {
+            let Some(child_h) = h(child) else {
+                continue;
+            };
+            if closed.contains(&(child, t + 1))
+                || !table.is_vertex_free(child, t + 1)
+                || !table.is_edge_free(cur, child, t)
+            {
+                continue;
+            }
+
+            parents.entry((child, t + 1)).or_insert(cur);
+            queue.push(Reverse((t + 1 + child_h, t + 1, child)));
+        }
+    }
+
+    None
+}
+
+/// Shortest path from start to goal in time, avoiding the reserved slots of `table`.
+///
+/// Every move along an edge, and every wait, takes one time step regardless of the edge weight.
+/// The returned path has one entry per time step, waits show up as repeated nodes, and the agent
+/// is able to stay at the goal once it has arrived.
+pub fn space_time_a_star(
+    g: &AdjacencyList,
+    start: Node,
+    goal: Node,
+    table: &ReservationTable,
+) -> Option<Path> {
+    let heuristic = hop_distances(&g.inverted(), goal);
+    space_time_a_star_impl(g, start, goal, &heuristic, table)
+}
+
+fn position(path: &Path, t: usize) -> Node {
+    path[t.min(path.len() - 1)].node
+}
+
+enum Conflict {
+    Vertex {
+        agents: (usize, usize),
+        node: Node,
+        time: usize,
+    },
+    Edge {
+        agents: (usize, usize),
+        from: Node,
+        to: Node,
+        time: usize,
+    },
+}
+
+fn first_conflict(paths: &[Path]) -> Option<Conflict> {
+    let end = paths.iter().map(|p| p.len()).max().unwrap_or(0);
+    for t in 0..end {
+        for i in 0..paths.len() {
+            for j in i + 1..paths.len() {
+                let (a, b) = (&paths[i], &paths[j]);
+                if position(a, t) == position(b, t) {
+                    return Some(Conflict::Vertex {
+                        agents: (i, j),
+                        node: position(a, t),
+                        time: t,
+                    });
+                }
+
+                let (from, to) = (position(a, t), position(a, t + 1));
+                if from != to && position(b, t) == to && position(b, t + 1) == from {
+                    return Some(Conflict::Edge {
+                        agents: (i, j),
+                        from,
+                        to,
+                        time: t,
+                    });
+                }
+            }
+        }
+    }
+    None
+}
+
+fn sum_of_costs(paths: &[Path]) -> usize {
+    paths.iter().map(|p| p.len() - 1).sum()
+}
+
+struct ConstraintNode {
+    constraints: Vec<ReservationTable>,
+    paths: Vec<Path>,
+}
+
+/// Find collision free paths for a set of agents with Conflict-Based Search.
+///
+/// Each agent is given as a (start, goal) pair and paths are in the same format as for
+/// `space_time_a_star`. Two agents can't be at the same node at the same time, nor swap places
+/// over an edge. The total number of time steps is minimized. Returns None if no solution is
+/// found within `max_expansions` expansions of the constraint tree.
+pub fn conflict_based_search(
+    g: &AdjacencyList,
+    agents: &[(Node, Node)],
+    max_expansions: usize,
+) -> Option<Vec<Path>> {
+    let inverted = g.inverted();
+    let heuristics: Vec<NodeMap<usize>> = agents
+        .iter()
+        .map(|&(_, goal)| hop_distances(&inverted, goal))
+        .collect();
+    let plan = |agent: usize, constraints: &ReservationTable| {
+        let (start, goal) = agents[agent];
+        space_time_a_star_impl(g, start, goal, &heuristics[agent], constraints)
+    };
+
+    let constraints = vec![ReservationTable::new(); agents.len()];
+    let paths = (0..agents.len())
+        .map(|i| plan(i, &constraints[i]))
+        .collect::<Option<Vec<Path>>>()?;
+
+    let mut tree = vec![ConstraintNode { constraints, paths }];
+    let mut queue = BinaryHeap::new();
+    queue.push(Reverse((sum_of_costs(&tree[0].paths), 0)));
+
+    let mut expansions = 0;
+    while let Some(Reverse((_, idx))) = queue.pop() {
+        let conflict = match first_conflict(&tree[idx].paths) {
+            None => return Some(std::mem::take(&mut tree[idx].paths)),
+            Some(c) => c,
+        };
+
+        expansions += 1;
+        if expansions > max_expansions {
+            return None;
+        }
+
+        let branches = match conflict {
+            Conflict::Vertex {
+                agents: (i, j),
+                node,
+                time,
+            } => [(i, node, node, time), (j, node, node, time)],
+            Conflict::Edge {
+                agents: (i, j),
+                from,
+                to,
+                time,
+            } => [(i, from, to, time), (j, to, from, time)],
+        };
+
+        for (agent, from, to, time) in branches {
+            let mut constraints = tree[idx].constraints.clone();
+            if from == to {
+                constraints[agent].reserve_vertex(from, time);
+            } else {
+                constraints[agent].reserve_edge(from, to, time);
+            }
+
+            if let Some(path) = plan(agent, &constraints[agent]) {
+                let mut paths = tree[idx].paths.clone();
+                paths[agent] = path;
+                queue.push(Reverse((sum_of_costs(&paths), tree.len())));
+                tree.push(ConstraintNode { constraints, paths });
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::{position, ReservationTable};
+    use crate::{AdjacencyList, Grid, Node, Path};
+
+    fn assert_collision_free(paths: &[Path]) {
+        let end = paths.iter().map(|p| p.len()).max().unwrap();
+        for t in 0..end {
+            for (i, a) in paths.iter().enumerate() {
+                for b in &paths[i + 1..] {
+                    assert_ne!(position(a, t), position(b, t), "Vertex conflict at {t}");
+                    let swap = position(a, t) == position(b, t + 1)
+                        && position(a, t + 1) == position(b, t)
+                        && position(a, t) != position(a, t + 1);
+                    assert!(!swap, "Swap conflict at {t}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn space_time_waits_for_reservation() {
+        let grid = Grid::new(3, 1, |_| true);
+        let g = grid.to_graph();
+        let mut table = ReservationTable::new();
+        table.reserve_vertex(grid.node((1, 0)), 1);
+        let path =
+            super::space_time_a_star(&g, grid.node((0, 0)), grid.node((2, 0)), &table).unwrap();
+        let nodes: Vec<Node> = path.iter().map(|e| e.node).collect();
+        assert_eq!(
+            nodes,
+            vec![
+                grid.node((0, 0)),
+                grid.node((0, 0)),
+                grid.node((1, 0)),
+                grid.node((2, 0))
+            ]
+        );
+    }
+
+    #[test]
+    fn space_time_goal_reserved_later() {
+        let mut g = AdjacencyList::new();
+        let a = g.add_node();
+        let b = g.add_node();
+        g.add_edge(a, b, 1.0);
+        g.add_edge(b, a, 1.0);
+        let mut table = ReservationTable::new();
+        table.reserve_vertex(b, 3);
+        let path = super::space_time_a_star(&g, a, b, &table).unwrap();
+        assert!(path.len() > 4);
+        assert_eq!(path.last().unwrap().node, b);
+    }
+
+    #[test]
+    fn cbs_crossing() {
+        // Two agents crossing in an open field
+        let grid = Grid::new(3, 3, |_| true);
+        let g = grid.to_graph();
+        let agents = [
+            (grid.node((0, 1)), grid.node((2, 1))),
+            (grid.node((1, 0)), grid.node((1, 2))),
+        ];
+        let paths = super::conflict_based_search(&g, &agents, 100).unwrap();
+        assert_collision_free(&paths);
+        assert_eq!(super::sum_of_costs(&paths), 4);
+    }
+
+    #[test]
+    fn cbs_swap_in_corridor_with_pocket() {
+        // .....
+        // ##.##
+        let grid = Grid::new(5, 2, |(x, y)| y == 0 || x == 2);
+        let g = grid.to_graph();
+        let agents = [
+            (grid.node((0, 0)), grid.node((4, 0))),
+            (grid.node((4, 0)), grid.node((0, 0))),
+        ];
+        let paths = super::conflict_based_search(&g, &agents, 1000).unwrap();
+        assert_collision_free(&paths);
+        for (path, (start, goal)) in paths.iter().zip(agents) {
+            assert_eq!(path.first().unwrap().node, start);
+            assert_eq!(path.last().unwrap().node, goal);
+        }
+    }
+
+    #[test]
+    fn cbs_swap_with_siding() {
+        // ...
+        // #.#
+        let grid = Grid::new(3, 2, |(x, y)| y == 0 || x == 1);
+        let g = grid.to_graph();
+        let agents = [
+            (grid.node((0, 0)), grid.node((2, 0))),
+            (grid.node((2, 0)), grid.node((0, 0))),
+        ];
+        let paths = super::conflict_based_search(&g, &agents, 1000).unwrap();
+        assert_collision_free(&paths);
+        // One agent steps into the siding while the other waits a step and then passes, 3 + 4
+        assert_eq!(super::sum_of_costs(&paths), 7);
+    }
+
+    #[test]
+    fn cbs_gives_up_after_max_expansions() {
+        // The agents can't swap in a corridor, so the constraint tree grows until the limit is
+        // reached
+        let grid = Grid::new(3, 1, |_| true);
+        let g = grid.to_graph();
+        let agents = [
+            (grid.node((0, 0)), grid.node((2, 0))),
+            (grid.node((2, 0)), grid.node((0, 0))),
+        ];
+        assert!(super::conflict_based_search(&g, &agents, 50).is_none());
+    }
+}
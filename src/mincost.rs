@@ -0,0 +1,274 @@
+use crate::flow::EPS;
+use crate::{AdjacencyList, Edge, Node, NodeMap, Weight};
+
+use std::collections::BinaryHeap;
+
+/// The result of `min_cost_flow`
+pub struct MinCostFlow {
+    pub cost: Weight,
+    /// The flow along each edge of the input graph, edges without flow are left out
+    pub flow: AdjacencyList,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MinCostFlowError {
+    /// The supplies and demands don't sum to zero
+    Unbalanced,
+    /// There is not enough capacity to route all supply to the demands
+    Infeasible,
+}
+
+impl std::fmt::Display for MinCostFlowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unbalanced => f.write_str("Supplies and demands don't sum to zero"),
+            Self::Infeasible => f.write_str("Not enough capacity to satisfy all demands"),
+        }
+    }
+}
+
+impl std::error::Error for MinCostFlowError {}
+
+/// Residual graph where edge 2 * i is the i:th added edge and 2 * i + 1 its reverse
+struct Network {
+    to: Vec<Node>,
+    cap: Vec<Weight>,
+    cost: Vec<Weight>,
+    adj: Vec<Vec<usize>>,
+}
+
+impl Network {
+    fn add_edge(&mut self, a: Node, b: Node, cap: Weight, cost: Weight) -> usize {
+        let idx = self.to.len();
+        self.adj[a.0].push(idx);
+        self.to.push(b);
+        self.cap.push(cap);
+        self.cost.push(cost);
+        self.adj[b.0].push(idx + 1);
+        self.to.push(a);
+        self.cap.push(0.0);
+        self.cost.push(-cost);
+        idx
+    }
+
+    fn push(&mut self, e: usize, amount: Weight) {
+        self.cap[e] -= amount;
+        self.cap[e ^ 1] += amount;
+    }
+}
+
+/// Minimum cost flow with successive shortest paths.
+///
+/// Edge weights in g are the cost per unit of flow and `capacity` gives the capacity of each
+/// edge. Nodes with positive supply are sources and nodes with negative supply are sinks, nodes
+/// not in `supply` have neither. Negative costs are allowed, also on cycles.
+pub fn min_cost_flow(
+    g: &AdjacencyList,
+    capacity: impl Fn(Node, &Edge) -> Weight,
+    supply: &NodeMap<Weight>,
+) -> Result<MinCostFlow, MinCostFlowError> {
+    let total: Weight = supply.iter().map(|(_, &s)| s).sum();
+    let scale: Weight = supply
+        .iter()
+        .map(|(_, &s)| s.abs())
+        .sum::<Weight>()
+        .max(1.0);
+    if total.abs() > EPS * scale {
+        return Err(MinCostFlowError::Unbalanced);
+    }
+
+    let len = g.len();
+    let (source, sink) = (Node(len), Node(len + 1));
+    let mut net = Network {
+        to: Vec::new(),
+        cap: Vec::new(),
+        cost: Vec::new(),
+        adj: vec![Vec::new(); len + 2],
+    };
+
+    // Negative cost edges start out saturated so that the residual graph has no negative costs,
+    // which moves their capacity from the supply of the head to the tail.
+    let mut balance: Vec<Weight> = (0..len)
+        .map(|i| supply.get(&Node(i)).copied().unwrap_or(0.0))
+        .collect();
+    let mut original: Vec<(Node, usize, Weight)> = Vec::new();
+    let mut cost = 0.0;
+    for n in g.nodes() {
+        for e in g.edges(n) {
+            let cap = capacity(n, e);
+            assert!(cap >= 0.0, "Negative capacity");
+            let idx = net.add_edge(n, e.node, cap, e.weight);
+            if e.weight < 0.0 {
+                net.push(idx, cap);
+                balance[n.0] -= cap;
+                balance[e.node.0] += cap;
+                cost += cap * e.weight;
+            }
+            original.push((n, idx, cap));
+        }
+    }
+
+    let mut required = 0.0;
+    for (i, &b) in balance.iter().enumerate() {
+        if b > EPS {
+            net.add_edge(source, Node(i), b, 0.0);
+            required += b;
+        } else if b < -EPS {
+            net.add_edge(Node(i), sink, -b, 0.0);
+        }
+    }
+
+    let mut potential: Vec<Weight> = vec![0.0; len + 2];
+    let mut dist: Vec<Weight> = vec![Weight::INFINITY; len + 2];
+    let mut parent: Vec<usize> = vec![usize::MAX; len + 2];
+    let mut sent = 0.0;
+    while required - sent > EPS * scale {
+        dist.iter_mut().for_each(|d| *d = Weight::INFINITY);
+        parent.iter_mut().for_each(|p| *p = usize::MAX);
+        let mut queue = BinaryHeap::new();
+        dist[source.0] = 0.0;
+        queue.push(Edge {
+            node: source,
+            weight: 0.0,
+        });
+        while let Some(Edge { node: cur, weight }) = queue.pop() {
+            if weight > dist[cur.0] {
+                continue;
+            }
+            for &e in &net.adj[cur.0] {
+                if net.cap[e] <= EPS {
+                    continue;
+                }
+                let child = net.to[e];
+                let reduced = (net.cost[e] + potential[cur.0] - potential[child.0]).max(0.0);
+                let d = dist[cur.0] + reduced;
+                if d < dist[child.0] {
+                    dist[child.0] = d;
+                    parent[child.0] = e;
+                    queue.push(Edge {
+                        node: child,
+                        weight: d,
+                    });
+                }
+            }
+        }
+
+        if dist[sink.0] == Weight::INFINITY {
+            return Err(MinCostFlowError::Infeasible);
+        }
+
+        for (p, &d) in potential.iter_mut().zip(&dist) {
+            if d < Weight::INFINITY {
+                *p += d;
+            }
+        }
+
+        let mut amount = Weight::INFINITY;
+        let mut n = sink;
+        while n != source {
+            let e = parent[n.0];
+            amount = amount.min(net.cap[e]);
+            n = net.to[e ^ 1];
+        }
+        let mut n = sink;
+        while n != source {
+            let e = parent[n.0];
+            net.push(e, amount);
+            cost += amount * net.cost[e];
+            n = net.to[e ^ 1];
+        }
+        sent += amount;
+    }
+
+    let mut flow = AdjacencyList::with_capacity(len);
+    for _ in 0..len {
+        flow.add_node();
+    }
+    for (from, idx, cap) in original {
+        let f = cap - net.cap[idx];
+        if f > EPS {
+            flow.add_edge(from, net.to[idx], f);
+        }
+    }
+
+    Ok(MinCostFlow { cost, flow })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{AdjacencyList, MinCostFlowError, Node, NodeMap};
+
+    fn supply(values: &[(usize, f32)]) -> NodeMap<f32> {
+        let mut m = NodeMap::new();
+        for &(n, s) in values {
+            m.insert(Node(n), s);
+        }
+        m
+    }
+
+    fn flow_on(g: &AdjacencyList, a: usize, b: usize) -> f32 {
+        g.edges(Node(a))
+            .find(|e| e.node == Node(b))
+            .map_or(0.0, |e| e.weight)
+    }
+
+    #[test]
+    fn cheap_and_expensive_routes() {
+        // 0 -> 1 -> 3 is cheap but only fits 2 units, 0 -> 2 -> 3 is expensive
+        let mut g = AdjacencyList::new();
+        let n: Vec<Node> = (0..4).map(|_| g.add_node()).collect();
+        g.add_edge(n[0], n[1], 1.0);
+        g.add_edge(n[1], n[3], 1.0);
+        g.add_edge(n[0], n[2], 3.0);
+        g.add_edge(n[2], n[3], 3.0);
+        let capacity = |from: Node, _: &crate::Edge| if from == n[1] { 2.0 } else { 10.0 };
+
+        let result = super::min_cost_flow(&g, capacity, &supply(&[(0, 5.0), (3, -5.0)])).unwrap();
+        assert_eq!(result.cost, 2.0 * 2.0 + 3.0 * 6.0);
+        assert_eq!(flow_on(&result.flow, 0, 1), 2.0);
+        assert_eq!(flow_on(&result.flow, 2, 3), 3.0);
+    }
+
+    #[test]
+    fn assignment() {
+        // Workers 0, 1 to jobs 2, 3, the diagonal is expensive
+        let mut g = AdjacencyList::new();
+        let n: Vec<Node> = (0..4).map(|_| g.add_node()).collect();
+        g.add_edge(n[0], n[2], 10.0);
+        g.add_edge(n[0], n[3], 1.0);
+        g.add_edge(n[1], n[2], 2.0);
+        g.add_edge(n[1], n[3], 10.0);
+        let s = supply(&[(0, 1.0), (1, 1.0), (2, -1.0), (3, -1.0)]);
+        let result = super::min_cost_flow(&g, |_, _| 1.0, &s).unwrap();
+        assert_eq!(result.cost, 3.0);
+        assert_eq!(flow_on(&result.flow, 0, 3), 1.0);
+        assert_eq!(flow_on(&result.flow, 1, 2), 1.0);
+    }
+
+    #[test]
+    fn negative_cycle() {
+        // 1 -> 2 -> 1 pays for itself, the cycle should be saturated
+        let mut g = AdjacencyList::new();
+        let n: Vec<Node> = (0..3).map(|_| g.add_node()).collect();
+        g.add_edge(n[0], n[1], 1.0);
+        g.add_edge(n[1], n[2], -3.0);
+        g.add_edge(n[2], n[1], 1.0);
+        let result = super::min_cost_flow(&g, |_, _| 4.0, &supply(&[(0, 1.0), (2, -1.0)])).unwrap();
+        assert_eq!(result.cost, 1.0 - 3.0 * 4.0 + 3.0);
+        assert_eq!(flow_on(&result.flow, 1, 2), 4.0);
+        assert_eq!(flow_on(&result.flow, 2, 1), 3.0);
+    }
+
+    #[test]
+    fn errors() {
+        let mut g = AdjacencyList::new();
+        let n: Vec<Node> = (0..3).map(|_| g.add_node()).collect();
+        g.add_edge(n[0], n[1], 1.0);
+        let unbalanced = super::min_cost_flow(&g, |_, _| 1.0, &supply(&[(0, 1.0)]));
+        assert_eq!(unbalanced.err(), Some(MinCostFlowError::Unbalanced));
+        let too_much = super::min_cost_flow(&g, |_, _| 1.0, &supply(&[(0, 2.0), (1, -2.0)]));
+        assert_eq!(too_much.err(), Some(MinCostFlowError::Infeasible));
+        let no_path = super::min_cost_flow(&g, |_, _| 1.0, &supply(&[(0, 1.0), (2, -1.0)]));
+        assert_eq!(no_path.err(), Some(MinCostFlowError::Infeasible));
+    }
+}
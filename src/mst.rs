@@ -0,0 +1,189 @@
+use crate::{
+    undirected_edges, undirected_graph, AdjacencyList, Edge, Node, NodeMap, NodeSet, NodeUnionFind,
+    Weight,
+};
+
+use std::collections::BinaryHeap;
+
+/// A minimum spanning forest, one tree per connected component
+pub struct SpanningForest {
+    /// The edges of the forest as (a, b, weight) with a < b
+    pub edges: Vec<(Node, Node, Weight)>,
+    /// The forest as a graph with the same nodes as the input and every edge in both directions
+    pub graph: AdjacencyList,
+    pub weight: Weight,
+}
+
+impl SpanningForest {
+    pub(crate) fn new(len: usize, mut edges: Vec<(Node, Node, Weight)>) -> Self {
+        edges.sort_by_key(|e| (e.0, e.1));
+        let graph = undirected_graph(len, &edges);
+        let weight = edges.iter().map(|e| e.2).sum();
+        Self {
+            edges,
+            graph,
+            weight,
+        }
+    }
+}
+
+fn by_weight(x: &(Node, Node, Weight), y: &(Node, Node, Weight)) -> std::cmp::Ordering {
+    x.2.partial_cmp(&y.2)
+        .expect("Invalid float")
+        .then_with(|| (x.0, x.1).cmp(&(y.0, y.1)))
+}
+
+/// Minimum spanning forest with Kruskal's algorithm. The graph is treated as undirected.
+pub fn kruskal(g: &AdjacencyList) -> SpanningForest {
+    let mut edges = undirected_edges(g);
+    edges.sort_by(by_weight);
+
+    let mut uf = NodeUnionFind::new(g);
+    let forest = edges
+        .into_iter()
+        .filter(|&(a, b, _)| uf.union(a, b))
+        .collect();
+    SpanningForest::new(g.len(), forest)
+}
+
+/// Minimum spanning forest with Prim's algorithm. The graph is treated as undirected.
+pub fn prim(g: &AdjacencyList) -> SpanningForest {
+    let undirected = undirected_graph(g.len(), &undirected_edges(g));
+    let mut in_tree = NodeSet::with_capacity(g.len());
+    let mut best: NodeMap<Edge> = NodeMap::with_capacity(g.len());
+    let mut queue: BinaryHeap<Edge> = BinaryHeap::new();
+    let mut forest = Vec::new();
+
+    for root in undirected.nodes() {
+        if in_tree.has(root) {
+            continue;
+        }
+
+        queue.push(Edge {
+            node: root,
+            weight: 0.0,
+        });
+        while let Some(Edge { node: cur, .. }) = queue.pop() {
+            if !in_tree.add(cur) {
+                continue;
+            }
+            if let Some(&Edge {
+                node: parent,
+                weight,
+            }) = best.get(&cur)
+            {
+                forest.push((parent.min(cur), parent.max(cur), weight));
+            }
+
+            for &Edge {
+                node: child,
+                weight,
+            } in undirected.edges(cur)
+            {
+                if in_tree.has(child) {
+                    continue;
+                }
+                if best.get(&child).is_none_or(|e| weight < e.weight) {
+                    best.insert(child, Edge { node: cur, weight });
+                    queue.push(Edge {
+                        node: child,
+                        weight,
+                    });
+                }
+            }
+        }
+    }
+
+    SpanningForest::new(g.len(), forest)
+}
+
+/// Minimum spanning forest with Borůvka's algorithm. The graph is treated as undirected.
+pub fn boruvka(g: &AdjacencyList) -> SpanningForest {
+    let edges = undirected_edges(g);
+    let mut uf = NodeUnionFind::new(g);
+    let mut forest = Vec::new();
+    let mut cheapest: Vec<Option<usize>> = vec![None; g.len()];
+
+    loop {
+        cheapest.iter_mut().for_each(|c| *c = None);
+        for (i, e) in edges.iter().enumerate() {
+            let (a, b) = (uf.find(e.0).0, uf.find(e.1).0);
+            if a == b {
+                continue;
+            }
+            for c in [a, b] {
+                let better = cheapest[c].is_none_or(|j| by_weight(e, &edges[j]).is_lt());
+                if better {
+                    cheapest[c] = Some(i);
+                }
+            }
+        }
+
+        let mut merged = false;
+        for &i in cheapest.iter().flatten() {
+            let e = edges[i];
+            if uf.union(e.0, e.1) {
+                forest.push(e);
+                merged = true;
+            }
+        }
+
+        if !merged {
+            break;
+        }
+    }
+
+    SpanningForest::new(g.len(), forest)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{AdjacencyList, Node, SpanningForest};
+
+    fn example() -> AdjacencyList {
+        // Two components, 0-4 and 5-6
+        let mut g = AdjacencyList::new();
+        let n: Vec<Node> = (0..7).map(|_| g.add_node()).collect();
+        g.add_edge(n[0], n[1], 7.0);
+        g.add_edge(n[0], n[3], 5.0);
+        g.add_edge(n[1], n[2], 8.0);
+        g.add_edge(n[1], n[3], 9.0);
+        g.add_edge(n[3], n[1], 9.0);
+        g.add_edge(n[1], n[4], 7.0);
+        g.add_edge(n[2], n[4], 5.0);
+        g.add_edge(n[3], n[4], 15.0);
+        g.add_edge(n[5], n[6], 1.0);
+        g
+    }
+
+    fn check(forest: &SpanningForest) {
+        let expected: Vec<(Node, Node, f32)> = vec![
+            (Node(0), Node(1), 7.0),
+            (Node(0), Node(3), 5.0),
+            (Node(1), Node(4), 7.0),
+            (Node(2), Node(4), 5.0),
+            (Node(5), Node(6), 1.0),
+        ];
+        assert_eq!(forest.edges, expected);
+        assert_eq!(forest.weight, 25.0);
+        assert_eq!(forest.graph.len(), 7);
+        assert!(forest.graph.has_edge(Node(4), Node(2)));
+        assert!(forest.graph.has_edge(Node(2), Node(4)));
+        assert!(!forest.graph.has_edge(Node(1), Node(2)));
+    }
+
+    #[test]
+    fn kruskal() {
+        check(&super::kruskal(&example()));
+    }
+
+    #[test]
+    fn prim() {
+        check(&super::prim(&example()));
+    }
+
+    #[test]
+    fn boruvka() {
+        check(&super::boruvka(&example()));
+    }
+}
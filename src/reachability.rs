@@ -0,0 +1,99 @@
+use crate::{connected_components, strongly_connected_components, AdjacencyList, Node, NodeMap};
+
+enum Labels {
+    /// Every edge has a reverse edge, connected components answer reachability exactly
+    Undirected(NodeMap<usize>),
+    Directed {
+        scc: NodeMap<usize>,
+        weak: NodeMap<usize>,
+    },
+}
+
+/// Precomputed component labels for rejecting queries between disconnected nodes in O(1).
+pub struct Reachability {
+    labels: Labels,
+}
+
+/// Every edge has a reverse edge. Edges are unique, so this holds exactly when the sorted edges
+/// and the sorted reversed edges are the same.
+fn is_symmetric(g: &AdjacencyList) -> bool {
+    let mut forward: Vec<(Node, Node)> = g
+        .nodes()
+        .flat_map(|n| g.edges(n).map(move |e| (n, e.node)))
+        .collect();
+    let mut backward: Vec<(Node, Node)> = forward.iter().map(|&(a, b)| (b, a)).collect();
+    forward.sort_unstable();
+    backward.sort_unstable();
+    forward == backward
+}
+
+impl Reachability {
+    pub fn new(g: &AdjacencyList) -> Self {
+        let labels = if is_symmetric(g) {
+            Labels::Undirected(connected_components(g))
+        } else {
+            Labels::Directed {
+                scc: strongly_connected_components(g),
+                weak: connected_components(g),
+            }
+        };
+        Self { labels }
+    }
+
+    /// Returns false if there is no path from a to b.
+    ///
+    /// For graphs where every edge has a reverse edge the answer is exact. For other graphs, true
+    /// only means that a path could not be ruled out, unless a and b are strongly connected.
+    pub fn may_reach(&self, a: Node, b: Node) -> bool {
+        match &self.labels {
+            Labels::Undirected(labels) => labels[a] == labels[b],
+            // SCC labels are in reverse topological order so a path can only go to lower labels
+            Labels::Directed { scc, weak } => weak[a] == weak[b] && scc[a] >= scc[b],
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{AdjacencyList, Node, Reachability};
+
+    #[test]
+    fn undirected() {
+        let mut g = AdjacencyList::new();
+        let n: Vec<Node> = (0..4).map(|_| g.add_node()).collect();
+        g.add_edge(n[0], n[1], 1.0);
+        g.add_edge(n[1], n[0], 1.0);
+        g.add_edge(n[2], n[3], 1.0);
+        g.add_edge(n[3], n[2], 1.0);
+        let r = Reachability::new(&g);
+        assert!(r.may_reach(n[0], n[1]));
+        assert!(r.may_reach(n[3], n[2]));
+        assert!(!r.may_reach(n[0], n[2]));
+        assert!(!r.may_reach(n[3], n[1]));
+    }
+
+    #[test]
+    fn directed() {
+        // 0 -> 1 -> 2, 3 -> 1, 4 alone
+        let mut g = AdjacencyList::new();
+        let n: Vec<Node> = (0..5).map(|_| g.add_node()).collect();
+        g.add_edge(n[0], n[1], 1.0);
+        g.add_edge(n[1], n[2], 1.0);
+        g.add_edge(n[3], n[1], 1.0);
+        let r = Reachability::new(&g);
+        assert!(r.may_reach(n[0], n[2]));
+        assert!(!r.may_reach(n[2], n[0]));
+        assert!(!r.may_reach(n[1], n[3]));
+        assert!(!r.may_reach(n[0], n[4]));
+    }
+
+    #[test]
+    fn a_star_rejects_unreachable() {
+        let grid = crate::Grid::new(5, 1, |c| c != (2, 0));
+        let g = grid.to_graph();
+        let mut acc = crate::AStarAcceleration::with_reachability(&g);
+        let (a, b, c) = (grid.node((0, 0)), grid.node((1, 0)), grid.node((4, 0)));
+        assert!(crate::a_star(&mut acc, a, c, crate::astar::NoHeuristic).is_none());
+        assert!(crate::a_star(&mut acc, a, b, crate::astar::NoHeuristic).is_some());
+    }
+}
@@ -0,0 +1,95 @@
+use crate::{AdjacencyList, Node, NodeMap};
+
+/// Label every node with its strongly connected component, using an iterative version of
+/// Tarjan's algorithm.
+///
+/// Components are numbered in reverse topological order: if there is a path from a node in
+/// component x to a node in a different component y, then x > y.
+pub fn strongly_connected_components(g: &AdjacencyList) -> NodeMap<usize> {
+    let len = g.len();
+    let mut index: Vec<usize> = vec![usize::MAX; len];
+    let mut lowlink: Vec<usize> = vec![0; len];
+    let mut on_stack: Vec<bool> = vec![false; len];
+    let mut stack: Vec<Node> = Vec::new();
+    // (node, index of the next edge to look at)
+    let mut call_stack: Vec<(Node, usize)> = Vec::new();
+    let mut labels = NodeMap::with_capacity(len);
+    let mut next_index = 0;
+    let mut next_label = 0;
+
+    for root in g.nodes() {
+        if index[root.0] != usize::MAX {
+            continue;
+        }
+
+        call_stack.push((root, 0));
+        index[root.0] = next_index;
+        lowlink[root.0] = next_index;
+        next_index += 1;
+        stack.push(root);
+        on_stack[root.0] = true;
+
+        while let Some(&mut (n, ref mut edge_idx)) = call_stack.last_mut() {
+            if let Some(e) = g.edges(n).nth(*edge_idx) {
+                *edge_idx += 1;
+                let child = e.node;
+                if index[child.0] == usize::MAX {
+                    index[child.0] = next_index;
+                    lowlink[child.0] = next_index;
+                    next_index += 1;
+                    stack.push(child);
+                    on_stack[child.0] = true;
+                    call_stack.push((child, 0));
+                } else if on_stack[child.0] {
+                    lowlink[n.0] = lowlink[n.0].min(index[child.0]);
+                }
+                continue;
+            }
+
+            call_stack.pop();
+            if let Some(&(parent, _)) = call_stack.last() {
+                lowlink[parent.0] = lowlink[parent.0].min(lowlink[n.0]);
+            }
+
+            if lowlink[n.0] == index[n.0] {
+                loop {
+                    let m = stack.pop().expect("Tarjan stack underflow");
+                    on_stack[m.0] = false;
+                    labels.insert(m, next_label);
+                    if m == n {
+                        break;
+                    }
+                }
+                next_label += 1;
+            }
+        }
+    }
+
+    labels
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{AdjacencyList, Node};
+
+    #[test]
+    fn scc() {
+        // 0 <-> 1 -> 2 <-> 3 -> 4, 5 alone
+        let mut g = AdjacencyList::new();
+        let n: Vec<Node> = (0..6).map(|_| g.add_node()).collect();
+        g.add_edge(n[0], n[1], 1.0);
+        g.add_edge(n[1], n[0], 1.0);
+        g.add_edge(n[1], n[2], 1.0);
+        g.add_edge(n[2], n[3], 1.0);
+        g.add_edge(n[3], n[2], 1.0);
+        g.add_edge(n[3], n[4], 1.0);
+
+        let labels = crate::strongly_connected_components(&g);
+        assert_eq!(labels[n[0]], labels[n[1]]);
+        assert_eq!(labels[n[2]], labels[n[3]]);
+        assert_ne!(labels[n[1]], labels[n[2]]);
+        assert!(labels[n[0]] > labels[n[2]]);
+        assert!(labels[n[2]] > labels[n[4]]);
+        assert_eq!(labels.iter().map(|(_, &l)| l).max(), Some(3));
+    }
+}
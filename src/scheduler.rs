@@ -0,0 +1,204 @@
+use crate::{topsort_dag, AdjacencyList, CycleError, Node, NodeMap};
+
+use std::any::Any;
+use std::collections::VecDeque;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Condvar, Mutex};
+
+/// The outcome of a node in `run_parallel`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TaskResult<T, E> {
+    Done(T),
+    Failed(E),
+    /// Never run since a node it depends on failed or was skipped
+    Skipped,
+}
+
+struct State<T, E> {
+    ready: VecDeque<Node>,
+    /// Incoming edges from nodes that are not resolved yet
+    incoming: Vec<usize>,
+    /// Some node this one depends on did not succeed
+    blocked: Vec<bool>,
+    results: Vec<Option<TaskResult<T, E>>>,
+    unresolved: usize,
+    /// A task panicked, the workers stop and the panic is passed on
+    panic: Option<Box<dyn Any + Send>>,
+}
+
+impl<T, E> State<T, E> {
+    /// Record the result of n and release or skip the nodes that depend on it
+    fn resolve(&mut self, g: &AdjacencyList, n: Node, result: TaskResult<T, E>) {
+        let mut stack = vec![(n, result)];
+        while let Some((n, result)) = stack.pop() {
+            let ok = matches!(result, TaskResult::Done(_));
+            self.results[n.0] = Some(result);
+            self.unresolved -= 1;
+            for e in g.edges(n) {
+                let child = e.node;
+                self.blocked[child.0] |= !ok;
+                self.incoming[child.0] -= 1;
+                if self.incoming[child.0] > 0 {
+                    continue;
+                }
+                if self.blocked[child.0] {
+                    stack.push((child, TaskResult::Skipped));
+                } else {
+                    self.ready.push_back(child);
+                }
+            }
+        }
+    }
+}
+
+/// Run `task` for every node of the DAG g on `threads` worker threads, where an edge a -> b means
+/// that b has to wait for a. Nodes are released with Kahn's algorithm as the nodes they depend
+/// on succeed. If a task fails, everything that depends on it is skipped. If a task panics, the
+/// remaining tasks are abandoned and the panic is passed on to the caller.
+pub fn run_parallel<T, E>(
+    g: &AdjacencyList,
+    threads: usize,
+    task: impl Fn(Node) -> Result<T, E> + Sync,
+) -> Result<NodeMap<TaskResult<T, E>>, CycleError>
+where
+    T: Send,
+    E: Send,
+{
+    assert!(threads > 0, "Need at least one thread");
+    topsort_dag(g)?;
+
+    let len = g.len();
+    let mut incoming = vec![0; len];
+    for n in g.nodes() {
+        for e in g.edges(n) {
+            incoming[e.node.0] += 1;
+        }
+    }
+    let state = Mutex::new(State {
+        ready: g.nodes().filter(|n| incoming[n.0] == 0).collect(),
+        incoming,
+        blocked: vec![false; len],
+        results: (0..len).map(|_| None).collect(),
+        unresolved: len,
+        panic: None,
+    });
+    let wakeup = Condvar::new();
+
+    std::thread::scope(|s| {
+        for _ in 0..threads {
+            s.spawn(|| {
+                let mut guard = state.lock().expect("Worker panicked");
+                loop {
+                    if guard.panic.is_some() {
+                        break;
+                    }
+                    if let Some(n) = guard.ready.pop_front() {
+                        drop(guard);
+                        let result = panic::catch_unwind(AssertUnwindSafe(|| task(n)));
+                        guard = state.lock().expect("Worker panicked");
+                        match result {
+                            Ok(Ok(t)) => guard.resolve(g, n, TaskResult::Done(t)),
+                            Ok(Err(e)) => guard.resolve(g, n, TaskResult::Failed(e)),
+                            Err(payload) => guard.panic = Some(payload),
+                        }
+                        wakeup.notify_all();
+                    } else if guard.unresolved == 0 {
+                        break;
+                    } else {
+                        guard = wakeup.wait(guard).expect("Worker panicked");
+                    }
+                }
+            });
+        }
+    });
+
+    let state = state.into_inner().expect("Worker panicked");
+    if let Some(payload) = state.panic {
+        panic::resume_unwind(payload);
+    }
+    let mut out = NodeMap::with_capacity(len);
+    for (i, result) in state.results.into_iter().enumerate() {
+        out.insert(Node(i), result.expect("Every node is resolved"));
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{AdjacencyList, CycleError, Node, NodeSet, TaskResult};
+
+    use std::sync::Mutex;
+
+    // 0 -> {1, 2} -> 3 -> 4, 5 -> 4 and the independent 6
+    fn example() -> AdjacencyList {
+        let mut g = AdjacencyList::new();
+        let n: Vec<Node> = (0..7).map(|_| g.add_node()).collect();
+        for (a, b) in [(0, 1), (0, 2), (1, 3), (2, 3), (3, 4), (5, 4)] {
+            g.add_edge(n[a], n[b], 1.0);
+        }
+        g
+    }
+
+    #[test]
+    fn dependencies_first() {
+        let g = example();
+        let preds = g.inverted();
+        let done = Mutex::new(NodeSet::new());
+        let results = super::run_parallel(&g, 4, |n| {
+            let finished = done.lock().unwrap();
+            assert!(preds.edges(n).all(|e| finished.has(e.node)));
+            drop(finished);
+            done.lock().unwrap().add(n);
+            Ok::<usize, ()>(n.0 * 10)
+        })
+        .unwrap();
+        for n in g.nodes() {
+            assert_eq!(results[n], TaskResult::Done(n.0 * 10));
+        }
+    }
+
+    #[test]
+    fn failure_skips_dependents() {
+        let g = example();
+        let results =
+            super::run_parallel(&g, 3, |n| if n == Node(2) { Err("broken") } else { Ok(()) })
+                .unwrap();
+        let expected = [
+            TaskResult::Done(()),
+            TaskResult::Done(()),
+            TaskResult::Failed("broken"),
+            TaskResult::Skipped,
+            TaskResult::Skipped,
+            TaskResult::Done(()),
+            TaskResult::Done(()),
+        ];
+        for n in g.nodes() {
+            assert_eq!(results[n], expected[n.0]);
+        }
+    }
+
+    #[test]
+    fn cycle() {
+        let mut g = example();
+        g.add_edge(Node(4), Node(0), 1.0);
+        let ran = Mutex::new(0);
+        let result = super::run_parallel(&g, 2, |_| {
+            *ran.lock().unwrap() += 1;
+            Ok::<(), ()>(())
+        });
+        assert_eq!(result.err(), Some(CycleError));
+        assert_eq!(*ran.lock().unwrap(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "task 3")]
+    fn panic_is_passed_on() {
+        let g = example();
+        let _ = super::run_parallel(&g, 2, |n| {
+            if n == Node(3) {
+                panic!("task 3");
+            }
+            Ok::<(), ()>(())
+        });
+    }
+}
@@ -1,6 +1,6 @@
 use crate::Node;
 // TODO: Bitset
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone)]
 pub struct NodeSet {
     v: Vec<bool>,
 }
@@ -0,0 +1,204 @@
+use crate::{edge, Grid, Path, Weight};
+
+/// A position on a grid where the tile (x, y) has its center at (x, y)
+pub type Point = (Weight, Weight);
+
+/// Shorten a path over grid nodes by skipping every node that the previous waypoint can see past.
+///
+/// Returns the new path, where the weights are the euclidean lengths of the segments, and its cost.
+pub fn string_pull(grid: &Grid, path: &Path) -> (Path, Weight) {
+    let mut out = Path::new();
+    let Some(first) = path.first() else {
+        return (out, 0.0);
+    };
+
+    out.push(edge(first.node, 0.0));
+    let mut cost = 0.0;
+    let mut anchor = first.node;
+    for (i, e) in path.iter().enumerate().skip(1) {
+        let visible = path
+            .get(i + 1)
+            .is_some_and(|next| grid.line_of_sight(grid.coords(anchor), grid.coords(next.node)));
+        if visible {
+            continue;
+        }
+
+        let w = grid.distance(grid.coords(anchor), grid.coords(e.node));
+        out.push(edge(e.node, w));
+        cost += w;
+        anchor = e.node;
+    }
+
+    (out, cost)
+}
+
+fn triarea2(a: Point, b: Point, c: Point) -> Weight {
+    let (ax, ay) = (b.0 - a.0, b.1 - a.1);
+    let (bx, by) = (c.0 - a.0, c.1 - a.1);
+    bx * ay - ax * by
+}
+
+fn distance(a: Point, b: Point) -> Weight {
+    ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt()
+}
+
+/// The shared boundaries of consecutive tiles on the path, as (left, right) pairs
+fn portals(grid: &Grid, path: &Path) -> Vec<(Point, Point)> {
+    let center = |e: &crate::Edge| {
+        let (x, y) = grid.coords(e.node);
+        (x as Weight, y as Weight)
+    };
+
+    let start = center(&path[0]);
+    let mut out = vec![(start, start)];
+    for w in path.windows(2) {
+        let (x0, y0) = center(&w[0]);
+        let (x1, y1) = center(&w[1]);
+        let (dx, dy) = (x1 - x0, y1 - y0);
+        let mid = (x0 + dx * 0.5, y0 + dy * 0.5);
+        if dx != 0.0 && dy != 0.0 {
+            // Diagonal steps go through the shared corner
+            out.push((mid, mid));
+        } else {
+            let (px, py) = (-dy * 0.5, dx * 0.5);
+            out.push(((mid.0 + px, mid.1 + py), (mid.0 - px, mid.1 - py)));
+        }
+    }
+    let end = center(path.last().expect("Non-empty path"));
+    out.push((end, end));
+    out
+}
+
+/// Shorten a path over grid nodes with the funnel algorithm.
+///
+/// The path is treated as a corridor of tiles and the result is the shortest polyline through
+/// that corridor. Unlike `string_pull` the waypoints are not restricted to tile centers, they
+/// are the tile corners that the path bends around. Returns the waypoints, including the start
+/// and end tile centers, and the length of the polyline.
+pub fn funnel(grid: &Grid, path: &Path) -> (Vec<Point>, Weight) {
+    if path.is_empty() {
+        return (Vec::new(), 0.0);
+    }
+
+    let portals = portals(grid, path);
+    let mut out = vec![portals[0].0];
+
+    let mut apex = portals[0].0;
+    let (mut left, mut right) = portals[0];
+    let (mut left_idx, mut right_idx) = (0, 0);
+
+    let mut i = 1;
+    while i < portals.len() {
+        let (l, r) = portals[i];
+
+        if triarea2(apex, right, r) <= 0.0 {
+            if apex == right || triarea2(apex, left, r) > 0.0 {
+                right = r;
+                right_idx = i;
+            } else {
+                // Right crossed over left, left is a corner of the path
+                out.push(left);
+                apex = left;
+                right = apex;
+                right_idx = left_idx;
+                i = left_idx + 1;
+                continue;
+            }
+        }
+
+        if triarea2(apex, left, l) >= 0.0 {
+            if apex == left || triarea2(apex, right, l) < 0.0 {
+                left = l;
+                left_idx = i;
+            } else {
+                // Left crossed over right, right is a corner of the path
+                out.push(right);
+                apex = right;
+                left = apex;
+                left_idx = right_idx;
+                i = right_idx + 1;
+                continue;
+            }
+        }
+
+        i += 1;
+    }
+
+    let end = portals.last().expect("Non-empty portals").0;
+    if out.last() != Some(&end) {
+        out.push(end);
+    }
+
+    let cost = out.windows(2).map(|w| distance(w[0], w[1])).sum();
+    (out, cost)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Grid, Node, Path};
+
+    // .....
+    // .###.
+    // .###.
+    // .....
+    fn walled() -> Grid {
+        Grid::new(5, 4, |(x, y)| !((1..4).contains(&x) && (1..3).contains(&y)))
+    }
+
+    fn grid_path(grid: &Grid, start: Node, goal: Node) -> Path {
+        let mut acc = crate::AStarAcceleration::new(&grid.to_graph());
+        crate::a_star(&mut acc, start, goal, crate::astar::NoHeuristic).unwrap()
+    }
+
+    #[test]
+    fn string_pull_open_field() {
+        let grid = Grid::new(6, 6, |_| true);
+        let path = grid_path(&grid, grid.node((0, 0)), grid.node((5, 2)));
+        let (pulled, cost) = super::string_pull(&grid, &path);
+        assert_eq!(pulled.len(), 2);
+        assert!((cost - grid.distance((0, 0), (5, 2))).abs() < 1e-5);
+    }
+
+    #[test]
+    fn string_pull_around_wall() {
+        let grid = walled();
+        let path = grid_path(&grid, grid.node((0, 3)), grid.node((4, 0)));
+        let before = crate::path_cost(&path);
+        let (pulled, cost) = super::string_pull(&grid, &path);
+        assert_eq!(pulled.first().unwrap().node, grid.node((0, 3)));
+        assert_eq!(pulled.last().unwrap().node, grid.node((4, 0)));
+        for w in pulled.windows(2) {
+            assert!(grid.line_of_sight(grid.coords(w[0].node), grid.coords(w[1].node)));
+        }
+        assert!(cost <= before + 1e-5);
+        assert!((cost - crate::path_cost(&pulled)).abs() < 1e-5);
+    }
+
+    #[test]
+    fn funnel_straight() {
+        let grid = Grid::new(6, 1, |_| true);
+        let path = grid_path(&grid, grid.node((0, 0)), grid.node((5, 0)));
+        let (points, cost) = super::funnel(&grid, &path);
+        assert_eq!(points, vec![(0.0, 0.0), (5.0, 0.0)]);
+        assert_eq!(cost, 5.0);
+    }
+
+    #[test]
+    fn funnel_around_corner() {
+        // ...
+        // ##.
+        // ##.
+        let grid = Grid::new(3, 3, |(x, y)| y == 0 || x == 2);
+        let path = grid_path(&grid, grid.node((0, 0)), grid.node((2, 2)));
+        let (points, cost) = super::funnel(&grid, &path);
+        assert_eq!(points, vec![(0.0, 0.0), (1.5, 0.5), (2.0, 2.0)]);
+        let expected = 1.5f32.hypot(0.5) + 0.5f32.hypot(1.5);
+        assert!((cost - expected).abs() < 1e-5);
+
+        // Same corner, walked in the other direction
+        let path = grid_path(&grid, grid.node((2, 2)), grid.node((0, 0)));
+        let (points, cost) = super::funnel(&grid, &path);
+        assert_eq!(points, vec![(2.0, 2.0), (1.5, 0.5), (0.0, 0.0)]);
+        assert!((cost - expected).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,164 @@
+use crate::dijkstra::dijkstra_impl;
+use crate::{
+    kruskal, undirected_edges, undirected_graph, walk_backwards, AdjacencyList, Edge, Node,
+    NodeMap, NodeSet, SpanningForest, Weight,
+};
+
+/// A tree that connects all `terminals` with a total weight at most twice the optimal Steiner
+/// tree. The graph is treated as undirected.
+///
+/// Uses the algorithm by Kou, Markowsky and Berman: a minimum spanning tree over the shortest
+/// path distances between the terminals is expanded to the paths in g, spanned again and pruned
+/// of leaves that are not terminals. Returns None if the terminals are not connected.
+pub fn steiner_tree(g: &AdjacencyList, terminals: &[Node]) -> Option<SpanningForest> {
+    let undirected = undirected_graph(g.len(), &undirected_edges(g));
+
+    // Metric closure of the terminals
+    let mut closure = AdjacencyList::with_capacity(terminals.len());
+    let mut parents: Vec<NodeMap<Edge>> = Vec::with_capacity(terminals.len());
+    for _ in terminals {
+        closure.add_node();
+    }
+    for (i, &a) in terminals.iter().enumerate() {
+        let mut cost = NodeMap::with_capacity(g.len());
+        let mut p = NodeMap::with_capacity(g.len());
+        dijkstra_impl(&undirected, &[a], None, |_, _| true, &mut cost, &mut p);
+        for (j, b) in terminals.iter().enumerate().skip(i + 1) {
+            if let Some(&c) = cost.get(b) {
+                closure.add_edge(Node(i), Node(j), c);
+            }
+        }
+        parents.push(p);
+    }
+
+    let closure_tree = kruskal(&closure);
+    if terminals.len() > 1 && closure_tree.edges.len() != terminals.len() - 1 {
+        return None;
+    }
+
+    // Expand to the graph, the paths may overlap
+    let mut expanded: Vec<(Node, Node, Weight)> = Vec::new();
+    for &(i, j, _) in &closure_tree.edges {
+        let (a, b) = (terminals[i.0], terminals[j.0]);
+        if a == b {
+            continue;
+        }
+        let path = walk_backwards(&a, &b, &parents[i.0]).expect("Terminals are connected");
+        for w in path.windows(2) {
+            expanded.push((w[0].node, w[1].node, w[1].weight));
+        }
+    }
+    let tree = kruskal(&undirected_graph(g.len(), &expanded));
+
+    // Prune leaves that are not terminals until there are none left
+    let mut is_terminal = NodeSet::with_capacity(g.len());
+    for &t in terminals {
+        is_terminal.add(t);
+    }
+    let mut degree: Vec<usize> = g.nodes().map(|n| tree.graph.edges(n).count()).collect();
+    let mut pruned = NodeSet::with_capacity(g.len());
+    let mut leaves: Vec<Node> = g
+        .nodes()
+        .filter(|&n| degree[n.0] == 1 && !is_terminal.has(n))
+        .collect();
+    while let Some(leaf) = leaves.pop() {
+        pruned.add(leaf);
+        let parent = tree
+            .graph
+            .edges(leaf)
+            .map(|e| e.node)
+            .find(|&m| !pruned.has(m))
+            .expect("A leaf has one edge left");
+        degree[parent.0] -= 1;
+        if degree[parent.0] == 1 && !is_terminal.has(parent) {
+            leaves.push(parent);
+        }
+    }
+
+    let edges = tree
+        .edges
+        .into_iter()
+        .filter(|&(a, b, _)| !pruned.has(a) && !pruned.has(b))
+        .collect();
+    Some(SpanningForest::new(g.len(), edges))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{AdjacencyList, Node};
+
+    fn graph(len: usize, edges: &[(usize, usize, f32)]) -> AdjacencyList {
+        let mut g = AdjacencyList::new();
+        let n: Vec<Node> = (0..len).map(|_| g.add_node()).collect();
+        for &(a, b, w) in edges {
+            g.add_edge(n[a], n[b], w);
+        }
+        g
+    }
+
+    #[test]
+    fn star() {
+        // Terminals 0, 1, 2 on the rim are best connected through the hub 3, which is shorter
+        // than the rim edges. 4 hangs off the hub and is pruned.
+        let g = graph(
+            5,
+            &[
+                (0, 3, 2.0),
+                (1, 3, 2.0),
+                (2, 3, 2.0),
+                (0, 1, 4.5),
+                (1, 2, 4.5),
+                (3, 4, 1.0),
+            ],
+        );
+        let tree = super::steiner_tree(&g, &[Node(0), Node(1), Node(2)]).unwrap();
+        assert_eq!(tree.weight, 6.0);
+        assert_eq!(
+            tree.edges,
+            vec![
+                (Node(0), Node(3), 2.0),
+                (Node(1), Node(3), 2.0),
+                (Node(2), Node(3), 2.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn grid_within_bound() {
+        let grid = crate::Grid::new(7, 7, |(x, y)| x != 3 || y == 6);
+        let g = grid.to_graph();
+        let terminals: Vec<Node> = [(0, 0), (6, 0), (0, 5), (6, 5)]
+            .into_iter()
+            .map(|c| grid.node(c))
+            .collect();
+        let tree = super::steiner_tree(&g, &terminals).unwrap();
+
+        // Connected tree with only terminals as leaves
+        let mut uf = crate::NodeUnionFind::new(&g);
+        for &(a, b, _) in &tree.edges {
+            assert!(uf.union(a, b));
+        }
+        for t in &terminals[1..] {
+            assert!(uf.connected(terminals[0], *t));
+        }
+        for n in g.nodes() {
+            if tree.graph.edges(n).count() == 1 {
+                assert!(terminals.contains(&n));
+            }
+        }
+
+        // Never worse than connecting every terminal to the first one by a shortest path
+        let cost = crate::multi_source_dijkstra(&g, &terminals[..1]);
+        let star: f32 = terminals[1..].iter().map(|t| cost[*t]).sum();
+        assert!(tree.weight <= star + 1e-3);
+    }
+
+    #[test]
+    fn disconnected_and_trivial() {
+        let g = graph(3, &[(0, 1, 1.0)]);
+        assert!(super::steiner_tree(&g, &[Node(0), Node(2)]).is_none());
+        let single = super::steiner_tree(&g, &[Node(1)]).unwrap();
+        assert!(single.edges.is_empty());
+        assert_eq!(single.weight, 0.0);
+    }
+}
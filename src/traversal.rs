@@ -0,0 +1,173 @@
+use crate::{AdjacencyList, Node, NodeSet};
+
+use std::collections::VecDeque;
+
+/// Lazy depth first traversal, yields each node reachable from the start nodes once.
+///
+/// The visit order matches `dfs`. Use `reset` to start over from other nodes without giving up
+/// the allocations.
+pub struct Dfs<'a> {
+    g: &'a AdjacencyList,
+    stack: VecDeque<Node>,
+    visited: NodeSet,
+}
+
+impl<'a> Dfs<'a> {
+    pub fn new(g: &'a AdjacencyList, start: Node) -> Self {
+        Self::from_nodes(g, [start])
+    }
+
+    /// Start from several nodes, the subtree of the first one is visited first
+    pub fn from_nodes(g: &'a AdjacencyList, starts: impl IntoIterator<Item = Node>) -> Self {
+        let mut dfs = Self {
+            g,
+            stack: VecDeque::new(),
+            visited: NodeSet::with_capacity(g.len()),
+        };
+        dfs.reset(starts);
+        dfs
+    }
+
+    /// Forget all visited nodes and start over from `starts`
+    pub fn reset(&mut self, starts: impl IntoIterator<Item = Node>) {
+        self.visited.clear();
+        self.stack.clear();
+        self.stack.extend(starts);
+        self.stack.make_contiguous().reverse();
+    }
+
+    /// The nodes yielded so far
+    pub fn visited(&self) -> &NodeSet {
+        &self.visited
+    }
+}
+
+impl Iterator for Dfs<'_> {
+    type Item = Node;
+
+    fn next(&mut self) -> Option<Node> {
+        while let Some(n) = self.stack.pop_back() {
+            if !self.visited.add(n) {
+                continue;
+            }
+            self.stack.extend(self.g.edges(n).map(|e| e.node));
+            return Some(n);
+        }
+        None
+    }
+}
+
+/// Lazy breadth first traversal, yields each node reachable from the start nodes once together
+/// with its depth, the number of edges from the closest start node.
+///
+/// Use `reset` to start over from other nodes without giving up the allocations.
+pub struct Bfs<'a> {
+    g: &'a AdjacencyList,
+    queue: VecDeque<(Node, usize)>,
+    visited: NodeSet,
+}
+
+impl<'a> Bfs<'a> {
+    pub fn new(g: &'a AdjacencyList, start: Node) -> Self {
+        Self::from_nodes(g, [start])
+    }
+
+    /// Start from several nodes, all of them at depth 0
+    pub fn from_nodes(g: &'a AdjacencyList, starts: impl IntoIterator<Item = Node>) -> Self {
+        let mut bfs = Self {
+            g,
+            queue: VecDeque::new(),
+            visited: NodeSet::with_capacity(g.len()),
+        };
+        bfs.reset(starts);
+        bfs
+    }
+
+    /// Forget all visited nodes and start over from `starts`
+    pub fn reset(&mut self, starts: impl IntoIterator<Item = Node>) {
+        self.visited.clear();
+        self.queue.clear();
+        for n in starts {
+            if self.visited.add(n) {
+                self.queue.push_back((n, 0));
+            }
+        }
+    }
+
+    /// The nodes yielded so far and the ones waiting in the queue
+    pub fn visited(&self) -> &NodeSet {
+        &self.visited
+    }
+}
+
+impl Iterator for Bfs<'_> {
+    type Item = (Node, usize);
+
+    fn next(&mut self) -> Option<(Node, usize)> {
+        let (n, depth) = self.queue.pop_front()?;
+        for e in self.g.edges(n) {
+            if self.visited.add(e.node) {
+                self.queue.push_back((e.node, depth + 1));
+            }
+        }
+        Some((n, depth))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{AdjacencyList, Bfs, Dfs, Node};
+
+    // 0 -> 1 -> 3, 0 -> 2 -> 3 -> 4 and 5 -> 4
+    fn example() -> AdjacencyList {
+        let mut g = AdjacencyList::new();
+        let n: Vec<Node> = (0..6).map(|_| g.add_node()).collect();
+        for (a, b) in [(0, 1), (0, 2), (1, 3), (2, 3), (3, 4), (5, 4)] {
+            g.add_edge(n[a], n[b], 1.0);
+        }
+        g
+    }
+
+    #[test]
+    fn dfs_matches_callback() {
+        let g = example();
+        let mut expected = Vec::new();
+        crate::dfs(&g, |n| expected.push(n));
+        let nodes: Vec<Node> = Dfs::from_nodes(&g, g.nodes()).collect();
+        assert_eq!(nodes, expected);
+
+        let mut dfs = Dfs::new(&g, Node(2));
+        assert_eq!(
+            dfs.by_ref().collect::<Vec<_>>(),
+            [Node(2), Node(3), Node(4)]
+        );
+        assert!(!dfs.visited().has(Node(0)));
+    }
+
+    #[test]
+    fn bfs_depth_and_early_exit() {
+        let g = example();
+        let levels: Vec<(Node, usize)> = Bfs::new(&g, Node(0)).collect();
+        assert_eq!(
+            levels,
+            [
+                (Node(0), 0),
+                (Node(1), 1),
+                (Node(2), 1),
+                (Node(3), 2),
+                (Node(4), 3)
+            ]
+        );
+
+        let mut bfs = Bfs::new(&g, Node(0));
+        assert_eq!(bfs.find(|&(n, _)| n == Node(2)), Some((Node(2), 1)));
+        assert!(!bfs.visited().has(Node(4)));
+
+        bfs.reset([Node(5), Node(1)]);
+        let levels: Vec<(Node, usize)> = bfs.collect();
+        assert_eq!(
+            levels,
+            [(Node(5), 0), (Node(1), 0), (Node(4), 1), (Node(3), 1)]
+        );
+    }
+}
@@ -0,0 +1,309 @@
+use crate::dijkstra::dijkstra_impl;
+use crate::{edge, walk_backwards, AdjacencyList, Node, NodeMap, Path, Weight};
+
+/// Waypoint counts up to this are solved exactly
+const HELD_KARP_LIMIT: usize = 12;
+
+/// Smallest improvement for a local search move to be taken, so that float noise can't make
+/// moves cycle
+const MIN_GAIN: Weight = 1e-4;
+
+/// The result of `tsp`
+pub struct Tour {
+    /// The waypoints in visiting order, starting with the first waypoint
+    pub order: Vec<Node>,
+    /// The full route through the graph
+    pub path: Path,
+    pub cost: Weight,
+}
+
+/// Shortest paths between every pair of waypoints, `paths[i][j]` is None if j can't be reached
+/// from i
+struct Distances {
+    cost: Vec<Vec<Weight>>,
+    paths: Vec<Vec<Option<Path>>>,
+}
+
+impl Distances {
+    fn new(g: &AdjacencyList, waypoints: &[Node]) -> Self {
+        let mut cost = Vec::with_capacity(waypoints.len());
+        let mut paths = Vec::with_capacity(waypoints.len());
+        for &a in waypoints {
+            let mut node_cost = NodeMap::with_capacity(g.len());
+            let mut parents = NodeMap::with_capacity(g.len());
+            dijkstra_impl(g, &[a], None, |_, _| true, &mut node_cost, &mut parents);
+            let row: Vec<Option<Path>> = waypoints
+                .iter()
+                .map(|b| {
+                    if a == *b {
+                        Some(vec![edge(a, 0.0)])
+                    } else {
+                        walk_backwards(&a, b, &parents)
+                    }
+                })
+                .collect();
+            cost.push(
+                waypoints
+                    .iter()
+                    .map(|b| node_cost.get(b).copied().unwrap_or(Weight::INFINITY))
+                    .collect(),
+            );
+            paths.push(row);
+        }
+        Self { cost, paths }
+    }
+
+    /// The cost of visiting the waypoints in `order`, and back to the first if `closed`
+    fn tour_cost(&self, order: &[usize], closed: bool) -> Weight {
+        let mut total: Weight = order.windows(2).map(|w| self.cost[w[0]][w[1]]).sum();
+        if closed {
+            total += self.cost[order[order.len() - 1]][order[0]];
+        }
+        total
+    }
+}
+
+/// Exact dynamic programming over subsets, O(2^n n^2)
+fn held_karp(d: &Distances, closed: bool) -> Vec<usize> {
+    let n = d.cost.len();
+    if n <= 2 {
+        return (0..n).collect();
+    }
+
+    // best[mask][j]: the cheapest way to start at 0, visit the waypoints 1.. in mask and end at
+    // j, which is in the mask. Bit i - 1 is waypoint i.
+    let full = 1usize << (n - 1);
+    let mut best = vec![vec![Weight::INFINITY; n]; full];
+    let mut parent = vec![vec![usize::MAX; n]; full];
+    for j in 1..n {
+        best[1 << (j - 1)][j] = d.cost[0][j];
+    }
+    for mask in 1..full {
+        for j in 1..n {
+            if mask & (1 << (j - 1)) == 0 || best[mask][j] == Weight::INFINITY {
+                continue;
+            }
+            for k in 1..n {
+                if mask & (1 << (k - 1)) != 0 {
+                    continue;
+                }
+                let next = mask | (1 << (k - 1));
+                let c = best[mask][j] + d.cost[j][k];
+                if c < best[next][k] {
+                    best[next][k] = c;
+                    parent[next][k] = j;
+                }
+            }
+        }
+    }
+
+    let ends = (1..n).map(|j| {
+        let back = if closed { d.cost[j][0] } else { 0.0 };
+        (j, best[full - 1][j] + back)
+    });
+    let (mut j, cost) = ends
+        .min_by(|a, b| a.1.partial_cmp(&b.1).expect("Invalid float"))
+        .expect("At least two waypoints");
+    if cost == Weight::INFINITY {
+        // Some waypoint can't be reached, any order is as bad
+        return (0..n).collect();
+    }
+
+    let mut order = Vec::with_capacity(n);
+    let mut mask = full - 1;
+    while j != usize::MAX && j != 0 {
+        order.push(j);
+        let p = parent[mask][j];
+        mask &= !(1 << (j - 1));
+        j = p;
+    }
+    order.push(0);
+    order.reverse();
+    order
+}
+
+/// Nearest neighbor construction followed by 2-opt and Or-opt moves until neither improves
+fn heuristic(d: &Distances, closed: bool) -> Vec<usize> {
+    let n = d.cost.len();
+    let mut order = Vec::with_capacity(n);
+    let mut visited = vec![false; n];
+    let mut cur = 0;
+    visited[0] = true;
+    order.push(0);
+    for _ in 1..n {
+        let next = (0..n)
+            .filter(|&j| !visited[j])
+            .min_by(|&a, &b| {
+                d.cost[cur][a]
+                    .partial_cmp(&d.cost[cur][b])
+                    .expect("Invalid float")
+            })
+            .expect("Unvisited waypoint left");
+        visited[next] = true;
+        order.push(next);
+        cur = next;
+    }
+
+    let cost = |a: usize, b: usize| d.cost[a][b];
+    // The waypoint after position k, None at the end of an open tour
+    let after = |order: &[usize], k: usize| {
+        if k + 1 < n {
+            Some(order[k + 1])
+        } else if closed {
+            Some(order[0])
+        } else {
+            None
+        }
+    };
+    let link = |a: usize, b: Option<usize>| b.map_or(0.0, |b| cost(a, b));
+
+    // Moves are compared by the edges they remove and add. Both sides are plain sums so that
+    // unreachable pairs compare as infinite instead of turning into NaN.
+    let mut improved = true;
+    while improved {
+        improved = false;
+
+        // 2-opt, reverse order[i..=j]. The cost of the segment is tracked in both directions
+        // since they differ for directed graphs.
+        for i in 1..n {
+            let (mut forward, mut backward) = (0.0, 0.0);
+            for j in i + 1..n {
+                forward += cost(order[j - 1], order[j]);
+                backward += cost(order[j], order[j - 1]);
+                let next = after(&order, j);
+                let removed = cost(order[i - 1], order[i]) + forward + link(order[j], next);
+                let added = cost(order[i - 1], order[j]) + backward + link(order[i], next);
+                if added < removed - MIN_GAIN {
+                    order[i..=j].reverse();
+                    std::mem::swap(&mut forward, &mut backward);
+                    improved = true;
+                }
+            }
+        }
+
+        // Or-opt, move a segment of up to three waypoints in between two others
+        for len in 1..=3.min(n - 1) {
+            for i in 1..=n - len {
+                let (first, last) = (order[i], order[i + len - 1]);
+                let prev = order[i - 1];
+                let next = after(&order, i + len - 1);
+                let cut = cost(prev, first) + link(last, next);
+                let bridge = link(prev, next);
+                let target = (0..n).filter(|&p| p + 1 < i || p >= i + len).find(|&p| {
+                    let q = after(&order, p);
+                    let removed = cut + link(order[p], q);
+                    let added = bridge + cost(order[p], first) + link(last, q);
+                    added < removed - MIN_GAIN
+                });
+                if let Some(p) = target {
+                    let segment: Vec<usize> = order.drain(i..i + len).collect();
+                    let at = if p < i { p + 1 } else { p + 1 - len };
+                    order.splice(at..at, segment);
+                    improved = true;
+                }
+            }
+        }
+    }
+
+    order
+}
+
+/// Find a short route that visits every waypoint, starting at the first one. If `closed`, the
+/// route returns to the first waypoint at the end.
+///
+/// Distances are shortest paths in g. Up to 12 waypoints the optimal order is found with the
+/// Held-Karp algorithm, otherwise with nearest neighbor construction improved by 2-opt and
+/// Or-opt. Returns None if some waypoint can't be reached.
+pub fn tsp(g: &AdjacencyList, waypoints: &[Node], closed: bool) -> Option<Tour> {
+    if waypoints.is_empty() {
+        return Some(Tour {
+            order: Vec::new(),
+            path: Path::new(),
+            cost: 0.0,
+        });
+    }
+
+    let d = Distances::new(g, waypoints);
+    let mut order = if waypoints.len() <= HELD_KARP_LIMIT {
+        held_karp(&d, closed)
+    } else {
+        heuristic(&d, closed)
+    };
+    let cost = d.tour_cost(&order, closed);
+    if cost == Weight::INFINITY {
+        return None;
+    }
+
+    if closed {
+        order.push(0);
+    }
+    let mut path = vec![edge(waypoints[0], 0.0)];
+    for w in order.windows(2) {
+        let leg = d.paths[w[0]][w[1]].as_ref()?;
+        path.extend_from_slice(&leg[1..]);
+    }
+    if closed {
+        order.pop();
+    }
+
+    Some(Tour {
+        order: order.into_iter().map(|i| waypoints[i]).collect(),
+        path,
+        cost,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{path_cost, Grid, Node};
+
+    #[test]
+    fn grid_round_trip() {
+        let grid = Grid::new(8, 8, |(x, y)| !(x == 4 && y < 6));
+        let g = grid.to_graph();
+        let waypoints: Vec<Node> = [(0, 0), (7, 0), (7, 7), (0, 7), (2, 3)]
+            .into_iter()
+            .map(|c| grid.node(c))
+            .collect();
+        let tour = super::tsp(&g, &waypoints, true).unwrap();
+        assert_eq!(tour.order[0], waypoints[0]);
+        assert_eq!(tour.order.len(), waypoints.len());
+        assert_eq!(tour.path.first().unwrap().node, waypoints[0]);
+        assert_eq!(tour.path.last().unwrap().node, waypoints[0]);
+        assert!((path_cost(&tour.path) - tour.cost).abs() < 1e-3);
+        for w in &waypoints {
+            assert!(tour.path.iter().any(|e| e.node == *w));
+        }
+        for step in tour.path.windows(2) {
+            assert!(g.has_edge(step[0].node, step[1].node));
+        }
+    }
+
+    #[test]
+    fn unreachable() {
+        let grid = Grid::new(5, 3, |(x, _)| x != 2);
+        let g = grid.to_graph();
+        let waypoints = [grid.node((0, 0)), grid.node((4, 0)), grid.node((0, 2))];
+        assert!(super::tsp(&g, &waypoints, false).is_none());
+        assert!(super::tsp(&g, &waypoints[..1], true).is_some());
+    }
+
+    #[test]
+    fn heuristic_close_to_exact() {
+        let mut rand = crate::test_rng(0x5851f42d4c957f2d);
+
+        let grid = Grid::new(20, 20, |_| true);
+        let g = grid.to_graph();
+        for round in 0..5 {
+            let waypoints: Vec<Node> = (0..9)
+                .map(|_| grid.node((rand(20) as usize, rand(20) as usize)))
+                .collect();
+            let closed = round % 2 == 0;
+            let d = super::Distances::new(&g, &waypoints);
+            let exact = d.tour_cost(&super::held_karp(&d, closed), closed);
+            let heuristic = d.tour_cost(&super::heuristic(&d, closed), closed);
+            assert!(heuristic >= exact - 1e-3);
+            assert!(heuristic <= exact * 1.1 + 1e-3, "round {round}");
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use crate::{AdjacencyList, Node, NodeMap};
+
+/// Disjoint sets of nodes, with path compression and union by rank
+#[derive(Debug, Clone)]
+pub struct NodeUnionFind {
+    parent: Vec<usize>,
+    rank: Vec<u8>,
+    count: usize,
+}
+
+impl NodeUnionFind {
+    /// Every node of g in a set of its own
+    pub fn new(g: &AdjacencyList) -> Self {
+        Self::with_len(g.len())
+    }
+
+    pub fn with_len(len: usize) -> Self {
+        Self {
+            parent: (0..len).collect(),
+            rank: vec![0; len],
+            count: len,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.parent.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parent.is_empty()
+    }
+
+    /// The number of disjoint sets
+    pub fn component_count(&self) -> usize {
+        self.count
+    }
+
+    /// The representative node of the set n is in
+    pub fn find(&mut self, n: Node) -> Node {
+        let mut i = n.0;
+        while self.parent[i] != i {
+            self.parent[i] = self.parent[self.parent[i]];
+            i = self.parent[i];
+        }
+        Node(i)
+    }
+
+    /// Merge the sets of a and b.
+    /// Returns true if they were not already in the same set.
+    pub fn union(&mut self, a: Node, b: Node) -> bool {
+        let (a, b) = (self.find(a).0, self.find(b).0);
+        if a == b {
+            return false;
+        }
+        let (a, b) = if self.rank[a] < self.rank[b] {
+            (b, a)
+        } else {
+            (a, b)
+        };
+        self.parent[b] = a;
+        if self.rank[a] == self.rank[b] {
+            self.rank[a] += 1;
+        }
+        self.count -= 1;
+        true
+    }
+
+    pub fn connected(&mut self, a: Node, b: Node) -> bool {
+        self.find(a) == self.find(b)
+    }
+
+    /// All sets, ordered by their smallest node. Nodes within a set are sorted.
+    pub fn components(&mut self) -> Vec<Vec<Node>> {
+        let mut index: NodeMap<usize> = NodeMap::with_capacity(self.len());
+        let mut out: Vec<Vec<Node>> = Vec::with_capacity(self.count);
+        for i in 0..self.len() {
+            let root = self.find(Node(i));
+            match index.get(&root) {
+                Some(&c) => out[c].push(Node(i)),
+                None => {
+                    index.insert(root, out.len());
+                    out.push(vec![Node(i)]);
+                }
+            }
+        }
+        out
+    }
+}
+
+/// Label every node with the index of its connected component, with edges treated as undirected.
+///
+/// Components are numbered from 0 in the order of their smallest node.
+pub fn connected_components(g: &AdjacencyList) -> NodeMap<usize> {
+    let mut uf = NodeUnionFind::new(g);
+    for n in g.nodes() {
+        for e in g.edges(n) {
+            uf.union(n, e.node);
+        }
+    }
+
+    let mut labels = NodeMap::with_capacity(g.len());
+    for (label, component) in uf.components().into_iter().enumerate() {
+        for n in component {
+            labels.insert(n, label);
+        }
+    }
+    labels
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{AdjacencyList, Node, NodeUnionFind};
+
+    #[test]
+    fn union_find() {
+        let mut uf = NodeUnionFind::with_len(6);
+        assert_eq!(uf.component_count(), 6);
+        assert!(uf.union(Node(0), Node(1)));
+        assert!(uf.union(Node(4), Node(1)));
+        assert!(!uf.union(Node(0), Node(4)));
+        assert!(uf.union(Node(2), Node(5)));
+        assert_eq!(uf.component_count(), 3);
+        assert!(uf.connected(Node(4), Node(0)));
+        assert!(!uf.connected(Node(3), Node(0)));
+
+        let components = uf.components();
+        assert_eq!(
+            components,
+            vec![
+                vec![Node(0), Node(1), Node(4)],
+                vec![Node(2), Node(5)],
+                vec![Node(3)]
+            ]
+        );
+    }
+
+    #[test]
+    fn connected_components() {
+        let mut g = AdjacencyList::new();
+        let n: Vec<Node> = (0..5).map(|_| g.add_node()).collect();
+        g.add_edge(n[3], n[0], 1.0);
+        g.add_edge(n[2], n[4], 1.0);
+        let labels = crate::connected_components(&g);
+        let actual: Vec<usize> = g.nodes().map(|n| labels[n]).collect();
+        assert_eq!(actual, vec![0, 1, 2, 0, 2]);
+    }
+}
@@ -0,0 +1,230 @@
+use crate::{AdjacencyList, Edge, Node, NodeMap};
+
+use std::collections::VecDeque;
+
+/// Returned from the `DfsVisitor` callbacks to steer the search
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Control {
+    Continue,
+    /// From `discover`, don't look at the edges of the node. From `tree_edge`, don't descend into
+    /// the child. Otherwise the same as `Continue`.
+    Prune,
+    /// End the whole search
+    Stop,
+}
+
+/// Callbacks for `dfs_visit`, all of them default to doing nothing. `from` is the node whose
+/// edge is being looked at and `e` the edge itself.
+pub trait DfsVisitor {
+    /// n is reached for the first time
+    fn discover(&mut self, _n: Node, _time: usize) -> Control {
+        Control::Continue
+    }
+
+    /// All descendants of n are done
+    fn finish(&mut self, _n: Node, _time: usize) -> Control {
+        Control::Continue
+    }
+
+    /// An edge to an undiscovered node, the node is discovered next
+    fn tree_edge(&mut self, _from: Node, _e: &Edge) -> Control {
+        Control::Continue
+    }
+
+    /// An edge to an ancestor that is not yet finished, i.e. an edge that closes a cycle
+    fn back_edge(&mut self, _from: Node, _e: &Edge) -> Control {
+        Control::Continue
+    }
+
+    /// An edge to a finished descendant that is not reached through a tree edge
+    fn forward_edge(&mut self, _from: Node, _e: &Edge) -> Control {
+        Control::Continue
+    }
+
+    /// An edge to a finished node in another subtree
+    fn cross_edge(&mut self, _from: Node, _e: &Edge) -> Control {
+        Control::Continue
+    }
+}
+
+/// Discovery and finish times from `dfs_visit`. A single clock is used for both so that
+/// `discovered[a] < discovered[b] < finished[b] < finished[a]` if b is a descendant of a.
+pub struct DfsTimes {
+    pub discovered: NodeMap<usize>,
+    /// Nodes that were discovered but never finished because the search was stopped are missing
+    pub finished: NodeMap<usize>,
+}
+
+/// Depth first search from each of `roots` in turn, skipping roots that are already discovered.
+///
+/// Edges are followed in reverse order so that the discovery order is the same as when pushing
+/// all children onto a stack.
+pub fn dfs_visit(
+    g: &AdjacencyList,
+    roots: impl IntoIterator<Item = Node>,
+    visitor: &mut impl DfsVisitor,
+) -> DfsTimes {
+    let mut discovered: NodeMap<usize> = NodeMap::with_capacity(g.len());
+    let mut finished: NodeMap<usize> = NodeMap::with_capacity(g.len());
+    let mut time = 0;
+    // The node and the number of its edges left to look at
+    let mut stack: VecDeque<(Node, usize)> = VecDeque::new();
+
+    'search: for root in roots {
+        if discovered.get(&root).is_some() {
+            continue;
+        }
+
+        stack.push_back((root, 0));
+        discovered.insert(root, time);
+        time += 1;
+        match visitor.discover(root, time - 1) {
+            Control::Continue => stack.back_mut().unwrap().1 = g.edges(root).count(),
+            Control::Prune => {}
+            Control::Stop => break,
+        }
+
+        while let Some((n, remaining)) = stack.back_mut() {
+            let n = *n;
+            if *remaining == 0 {
+                stack.pop_back();
+                finished.insert(n, time);
+                time += 1;
+                if visitor.finish(n, time - 1) == Control::Stop {
+                    break 'search;
+                }
+                continue;
+            }
+
+            *remaining -= 1;
+            let e = g.edges(n).nth(*remaining).expect("Edge index in range");
+            let child = e.node;
+            let control = match (discovered.get(&child), finished.get(&child)) {
+                (None, _) => match visitor.tree_edge(n, e) {
+                    Control::Continue => {
+                        discovered.insert(child, time);
+                        time += 1;
+                        let control = visitor.discover(child, time - 1);
+                        let edges = match control {
+                            Control::Continue => g.edges(child).count(),
+                            _ => 0,
+                        };
+                        stack.push_back((child, edges));
+                        control
+                    }
+                    control => control,
+                },
+                (Some(_), None) => visitor.back_edge(n, e),
+                (Some(&d), Some(_)) if discovered[n] < d => visitor.forward_edge(n, e),
+                (Some(_), Some(_)) => visitor.cross_edge(n, e),
+            };
+            if control == Control::Stop {
+                break 'search;
+            }
+        }
+    }
+
+    DfsTimes {
+        discovered,
+        finished,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{AdjacencyList, Control, DfsVisitor, Edge, Node};
+
+    #[derive(Default)]
+    struct Recorder {
+        events: Vec<String>,
+        prune: Option<Node>,
+        stop: Option<Node>,
+    }
+
+    impl DfsVisitor for Recorder {
+        fn discover(&mut self, n: Node, _time: usize) -> Control {
+            self.events.push(format!("d{}", n.0));
+            if Some(n) == self.stop {
+                Control::Stop
+            } else if Some(n) == self.prune {
+                Control::Prune
+            } else {
+                Control::Continue
+            }
+        }
+
+        fn finish(&mut self, n: Node, _time: usize) -> Control {
+            self.events.push(format!("f{}", n.0));
+            Control::Continue
+        }
+
+        fn tree_edge(&mut self, from: Node, e: &Edge) -> Control {
+            self.events.push(format!("t{}{}", from.0, e.node.0));
+            Control::Continue
+        }
+
+        fn back_edge(&mut self, from: Node, e: &Edge) -> Control {
+            self.events.push(format!("b{}{}", from.0, e.node.0));
+            Control::Continue
+        }
+
+        fn forward_edge(&mut self, from: Node, e: &Edge) -> Control {
+            self.events.push(format!("w{}{}", from.0, e.node.0));
+            Control::Continue
+        }
+
+        fn cross_edge(&mut self, from: Node, e: &Edge) -> Control {
+            self.events.push(format!("c{}{}", from.0, e.node.0));
+            Control::Continue
+        }
+    }
+
+    // 0 -> 1 -> 2 -> 0 is a cycle, 0 -> 2 is a forward edge once 2 is done and 3 -> 1 a cross edge
+    fn example() -> AdjacencyList {
+        let mut g = AdjacencyList::new();
+        let n: Vec<Node> = (0..4).map(|_| g.add_node()).collect();
+        g.add_edge(n[0], n[2], 1.0);
+        g.add_edge(n[0], n[1], 1.0);
+        g.add_edge(n[1], n[2], 1.0);
+        g.add_edge(n[2], n[0], 1.0);
+        g.add_edge(n[3], n[1], 1.0);
+        g
+    }
+
+    #[test]
+    fn classification() {
+        let g = example();
+        let mut rec = Recorder::default();
+        let times = super::dfs_visit(&g, g.nodes(), &mut rec);
+        let expected = [
+            "d0", "t01", "d1", "t12", "d2", "b20", "f2", "f1", "w02", "f0", "d3", "c31", "f3",
+        ];
+        assert_eq!(rec.events, expected);
+        assert_eq!(times.discovered[Node(2)], 2);
+        assert_eq!(times.finished[Node(2)], 3);
+        assert_eq!(times.finished[Node(0)], 5);
+        assert_eq!(times.finished[Node(3)], 7);
+    }
+
+    #[test]
+    fn prune_and_stop() {
+        let g = example();
+        let mut rec = Recorder {
+            prune: Some(Node(1)),
+            ..Default::default()
+        };
+        super::dfs_visit(&g, [Node(0)], &mut rec);
+        assert_eq!(
+            rec.events,
+            ["d0", "t01", "d1", "f1", "t02", "d2", "b20", "f2", "f0"]
+        );
+
+        let mut rec = Recorder {
+            stop: Some(Node(2)),
+            ..Default::default()
+        };
+        let times = super::dfs_visit(&g, g.nodes(), &mut rec);
+        assert_eq!(rec.events, ["d0", "t01", "d1", "t12", "d2"]);
+        assert!(times.finished.get(&Node(0)).is_none());
+    }
+}
@@ -0,0 +1,128 @@
+use crate::dijkstra::shortest_path_filtered;
+use crate::{path_cost, shortest_path, AdjacencyList, Edge, Node, NodeSet, Path};
+
+use std::collections::HashSet;
+
+fn same_nodes(a: &[Edge], b: &[Edge]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(x, y)| x.node == y.node)
+}
+
+/// Find the `k` shortest loopless paths from start to end with Yen's algorithm.
+///
+/// The paths are returned in order of increasing cost, fewer than `k` are returned if there are
+/// no more paths. Edges and nodes are masked out during the searches, the graph is never
+/// modified.
+pub fn k_shortest_paths(g: &AdjacencyList, start: Node, end: Node, k: usize) -> Vec<Path> {
+    let mut out: Vec<Path> = Vec::new();
+    if k == 0 {
+        return out;
+    }
+    let Some(first) = shortest_path(g, start, end) else {
+        return out;
+    };
+    out.push(first);
+
+    let mut candidates: Vec<Path> = Vec::new();
+    let mut removed_nodes = NodeSet::with_capacity(g.len());
+    let mut removed_edges: HashSet<(Node, Node)> = HashSet::new();
+
+    while out.len() < k {
+        let prev = out.last().expect("At least one path");
+        for i in 0..prev.len() - 1 {
+            let spur = prev[i].node;
+            let root = &prev[..=i];
+
+            removed_edges.clear();
+            for p in &out {
+                if p.len() > i + 1 && same_nodes(&p[..=i], root) {
+                    removed_edges.insert((p[i].node, p[i + 1].node));
+                }
+            }
+
+            removed_nodes.clear();
+            for e in &root[..i] {
+                removed_nodes.add(e.node);
+            }
+
+            let allowed = |from: Node, e: &Edge| {
+                !removed_nodes.has(e.node) && !removed_edges.contains(&(from, e.node))
+            };
+            let Some(spur_path) = shortest_path_filtered(g, spur, end, allowed) else {
+                continue;
+            };
+
+            let mut candidate = root.to_vec();
+            candidate.extend_from_slice(&spur_path[1..]);
+            let known = candidates
+                .iter()
+                .chain(out.iter())
+                .any(|p| same_nodes(p, &candidate));
+            if !known {
+                candidates.push(candidate);
+            }
+        }
+
+        let best = candidates
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| {
+                path_cost(a)
+                    .partial_cmp(&path_cost(b))
+                    .expect("Invalid float")
+            })
+            .map(|(i, _)| i);
+        match best {
+            Some(i) => out.push(candidates.swap_remove(i)),
+            None => break,
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{path_cost, AdjacencyList, Node};
+
+    // Example from the wikipedia article on Yen's algorithm
+    fn example() -> AdjacencyList {
+        let mut g = AdjacencyList::new();
+        let n: Vec<Node> = (0..6).map(|_| g.add_node()).collect();
+        let (c, d, e, f, g_, h) = (n[0], n[1], n[2], n[3], n[4], n[5]);
+        g.add_edge(c, d, 3.0);
+        g.add_edge(c, e, 2.0);
+        g.add_edge(d, f, 4.0);
+        g.add_edge(e, d, 1.0);
+        g.add_edge(e, f, 2.0);
+        g.add_edge(e, g_, 3.0);
+        g.add_edge(f, g_, 2.0);
+        g.add_edge(f, h, 1.0);
+        g.add_edge(g_, h, 2.0);
+        g
+    }
+
+    #[test]
+    fn wikipedia_example() {
+        let g = example();
+        let paths = super::k_shortest_paths(&g, Node(0), Node(5), 3);
+        let nodes: Vec<Vec<usize>> = paths
+            .iter()
+            .map(|p| p.iter().map(|e| e.node.0).collect())
+            .collect();
+        // The third path ties with 0, 2, 3, 4, 5
+        assert_eq!(nodes[..2], vec![vec![0, 2, 3, 5], vec![0, 2, 4, 5]]);
+        let costs: Vec<f32> = paths.iter().map(path_cost).collect();
+        assert_eq!(costs, vec![5.0, 7.0, 8.0]);
+    }
+
+    #[test]
+    fn fewer_than_k() {
+        let g = example();
+        let paths = super::k_shortest_paths(&g, Node(0), Node(5), 100);
+        assert_eq!(paths.len(), 7);
+        for w in paths.windows(2) {
+            assert!(path_cost(&w[0]) <= path_cost(&w[1]));
+        }
+        assert!(super::k_shortest_paths(&g, Node(5), Node(0), 3).is_empty());
+    }
+}